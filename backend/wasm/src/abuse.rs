@@ -0,0 +1,313 @@
+//! ボット・不正閲覧の検出
+//!
+//! 閲覧レコードから次のパターンを検出し、除外した閲覧と投稿ごとの疑わしさを返す。
+//!
+//! - 連続閲覧: 1ユーザーが1投稿を短時間に大量に閲覧（上限を超えた分を除外）
+//! - 均一な間隔: 閲覧間隔がほぼ一定の機械的なアクセス（最初の1件以外を除外）
+//! - 多投稿の巡回: 1ユーザーが数秒で多数の投稿を閲覧（該当期間の閲覧を除外）
+//! - デバイス・国の偏り: 投稿の閲覧がサイト全体と比べて1つのフラグに集中
+//!   （どの閲覧が不正か区別できないため除外はせず、疑わしさにのみ反映）
+//!
+//! 疑わしさは0〜1で、`TrendCalculator::set_suspicion`でスコアの割引に使える。
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+
+use crate::config::{AbuseConfig, ScoringConfig};
+use crate::packing::ViewRecord;
+use crate::trend_calculator::ViewEvent;
+
+/// 検出されたパターン
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum AbuseReason {
+    BurstRate,
+    UniformIntervals,
+    MultiPostSpree,
+    FlagCluster,
+}
+
+/// 不正と判定されたユーザー
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FlaggedUser {
+    pub user_id: u32,
+    pub post_id: Option<u32>, // 多投稿の巡回の場合はNone
+    pub reason: AbuseReason,
+    pub removed_views: u32,
+}
+
+/// 投稿ごとの疑わしさ
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PostSuspicion {
+    pub post_id: u32,
+    pub total_views: u32,
+    pub removed_views: u32,
+    pub suspicion: f64, // 0〜1
+    pub reasons: Vec<AbuseReason>,
+}
+
+/// 検出結果
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct AbuseReport {
+    pub cleaned: Vec<ViewRecord>, // 不正な閲覧を除いたレコード（入力順）
+    pub posts: Vec<PostSuspicion>,
+    pub flagged_users: Vec<FlaggedUser>,
+}
+
+/// 不正なイベントを除いた1投稿分のイベント
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CleanedEvents {
+    pub events: Vec<ViewEvent>,
+    pub removed_events: u32,
+    pub suspicion: f64,
+}
+
+/// ボット・不正閲覧の検出器
+#[derive(Default)]
+pub struct AbuseDetector {
+    config: Arc<ScoringConfig>,
+}
+
+impl AbuseDetector {
+    /// 既定の設定で検出器を作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// スコア計算の設定を差し替える（`abuse`の項目を使う）
+    pub fn with_config(mut self, config: Arc<ScoringConfig>) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// 複数投稿の閲覧レコードから不正な閲覧を検出する
+    pub fn detect(&self, records: &[ViewRecord]) -> AbuseReport {
+        let (removed, flagged) = self.classify(records);
+        let posts = post_suspicions(records, &removed, &self.config.abuse);
+        let cleaned = records
+            .iter()
+            .zip(&removed)
+            .filter(|(_, reason)| reason.is_none())
+            .map(|(record, _)| *record)
+            .collect();
+        let flagged_users = flagged
+            .into_iter()
+            .map(|((user_id, post_id, reason), removed_views)| FlaggedUser {
+                user_id,
+                post_id,
+                reason,
+                removed_views,
+            })
+            .collect();
+
+        AbuseReport {
+            cleaned,
+            posts,
+            flagged_users,
+        }
+    }
+
+    /// 1投稿分のイベントから不正なイベントを除外する
+    ///
+    /// イベントには投稿・デバイスの情報がないため、連続閲覧と均一な間隔のみを判定する。
+    pub fn clean_events(&self, events: &[ViewEvent]) -> CleanedEvents {
+        let records: Vec<ViewRecord> = events
+            .iter()
+            .map(|e| ViewRecord {
+                post_id: 0,
+                user_id: e.user_id,
+                timestamp: e.timestamp,
+                device: 0,
+                country: 0,
+            })
+            .collect();
+        let (removed, _) = self.classify(&records);
+        let removed_events = removed.iter().filter(|reason| reason.is_some()).count() as u32;
+
+        CleanedEvents {
+            events: events
+                .iter()
+                .zip(&removed)
+                .filter(|(_, reason)| reason.is_none())
+                .map(|(event, _)| event.clone())
+                .collect(),
+            removed_events,
+            suspicion: if events.is_empty() {
+                0.0
+            } else {
+                removed_events as f64 / events.len() as f64
+            },
+        }
+    }
+
+    /// レコードごとの除外理由と、(ユーザー, 投稿, 理由)ごとの除外数を求める
+    fn classify(&self, records: &[ViewRecord]) -> (Vec<Option<AbuseReason>>, BTreeMap<FlagKey, u32>) {
+        let config = &self.config.abuse;
+        let mut removed = vec![None; records.len()];
+        let mut flagged: BTreeMap<FlagKey, u32> = BTreeMap::new();
+
+        // (投稿, ユーザー)ごと・ユーザーごとの時系列
+        let mut by_post_user: HashMap<(u32, u32), Vec<usize>> = HashMap::new();
+        let mut by_user: HashMap<u32, Vec<usize>> = HashMap::new();
+        for (index, record) in records.iter().enumerate() {
+            by_post_user.entry((record.post_id, record.user_id)).or_default().push(index);
+            by_user.entry(record.user_id).or_default().push(index);
+        }
+
+        for ((post_id, user_id), mut indices) in by_post_user {
+            indices.sort_by_key(|i| records[*i].timestamp);
+            let timestamps: Vec<u64> = indices.iter().map(|i| records[*i].timestamp).collect();
+
+            for (reason, hits) in [
+                (AbuseReason::UniformIntervals, uniform_intervals(&timestamps, config)),
+                (AbuseReason::BurstRate, burst_excess(&timestamps, config)),
+            ] {
+                let count = mark(&indices, &hits, reason, &mut removed);
+                if count > 0 {
+                    *flagged.entry((user_id, Some(post_id), reason)).or_default() += count;
+                }
+            }
+        }
+
+        for (user_id, mut indices) in by_user {
+            indices.sort_by_key(|i| records[*i].timestamp);
+            let hits = spree(&indices, records, config);
+            let count = mark(&indices, &hits, AbuseReason::MultiPostSpree, &mut removed);
+            if count > 0 {
+                *flagged.entry((user_id, None, AbuseReason::MultiPostSpree)).or_default() += count;
+            }
+        }
+
+        (removed, flagged)
+    }
+}
+
+/// (ユーザー, 投稿, 理由)
+type FlagKey = (u32, Option<u32>, AbuseReason);
+
+/// 判定結果の位置を除外済みにし、新たに除外した件数を返す
+fn mark(indices: &[usize], hits: &[bool], reason: AbuseReason, removed: &mut [Option<AbuseReason>]) -> u32 {
+    let mut count = 0;
+    for (index, hit) in indices.iter().zip(hits) {
+        if *hit && removed[*index].is_none() {
+            removed[*index] = Some(reason);
+            count += 1;
+        }
+    }
+    count
+}
+
+/// 期間内の閲覧数が上限を超えた分
+fn burst_excess(timestamps: &[u64], config: &AbuseConfig) -> Vec<bool> {
+    let mut hits = vec![false; timestamps.len()];
+    let mut start = 0;
+    for (i, timestamp) in timestamps.iter().enumerate() {
+        while start < i && timestamps[start].saturating_add(config.burst_window_ms) <= *timestamp {
+            start += 1;
+        }
+        hits[i] = (i - start + 1) as u32 > config.max_views_per_burst;
+    }
+    hits
+}
+
+/// 閲覧間隔がほぼ一定なら最初の1件以外
+fn uniform_intervals(timestamps: &[u64], config: &AbuseConfig) -> Vec<bool> {
+    let mut hits = vec![false; timestamps.len()];
+    if timestamps.len() < config.uniform_min_views.max(3) as usize {
+        return hits;
+    }
+
+    let gaps: Vec<f64> = timestamps.windows(2).map(|w| (w[1] - w[0]) as f64).collect();
+    let mean = gaps.iter().sum::<f64>() / gaps.len() as f64;
+    if mean <= 0.0 {
+        return hits;
+    }
+    let variance = gaps.iter().map(|g| (g - mean).powi(2)).sum::<f64>() / gaps.len() as f64;
+    if variance.sqrt() / mean <= config.uniform_max_cv {
+        hits.iter_mut().skip(1).for_each(|hit| *hit = true);
+    }
+    hits
+}
+
+/// 期間内に上限を超える数の投稿を閲覧した区間の閲覧
+fn spree(indices: &[usize], records: &[ViewRecord], config: &AbuseConfig) -> Vec<bool> {
+    let mut hits = vec![false; indices.len()];
+    let mut posts: HashMap<u32, u32> = HashMap::new();
+    let mut start = 0;
+    for end in 0..indices.len() {
+        let timestamp = records[indices[end]].timestamp;
+        *posts.entry(records[indices[end]].post_id).or_default() += 1;
+
+        while start < end && records[indices[start]].timestamp.saturating_add(config.spree_window_ms) < timestamp {
+            let post_id = records[indices[start]].post_id;
+            if let Some(count) = posts.get_mut(&post_id) {
+                *count -= 1;
+                if *count == 0 {
+                    posts.remove(&post_id);
+                }
+            }
+            start += 1;
+        }
+
+        if posts.len() as u32 > config.max_posts_per_spree {
+            hits[start..=end].iter_mut().for_each(|hit| *hit = true);
+        }
+    }
+    hits
+}
+
+/// 投稿ごとの疑わしさを計算
+fn post_suspicions(records: &[ViewRecord], removed: &[Option<AbuseReason>], config: &AbuseConfig) -> Vec<PostSuspicion> {
+    // 投稿ごとの閲覧数・除外数・除外理由・フラグ別の閲覧数
+    let mut posts: BTreeMap<u32, PostTally> = BTreeMap::new();
+    let mut site_flags: HashMap<u8, u32> = HashMap::new();
+    for (record, reason) in records.iter().zip(removed) {
+        let flag = record.device << 4 | record.country;
+        let tally = posts.entry(record.post_id).or_default();
+        tally.total += 1;
+        if let Some(reason) = reason {
+            tally.removed += 1;
+            tally.reasons.insert(*reason);
+        }
+        *tally.flags.entry(flag).or_default() += 1;
+        *site_flags.entry(flag).or_default() += 1;
+    }
+
+    posts
+        .into_iter()
+        .map(|(post_id, mut tally)| {
+            // 1つのフラグへの偏り（サイト全体の割合との差で判定）
+            let clustered = tally.total >= config.cluster_min_views
+                && tally.flags.iter().any(|(flag, count)| {
+                    let share = *count as f64 / tally.total as f64;
+                    let site_share = site_flags[flag] as f64 / records.len() as f64;
+                    share >= config.cluster_min_share && share - site_share >= config.cluster_min_excess
+                });
+            if clustered {
+                tally.reasons.insert(AbuseReason::FlagCluster);
+            }
+
+            let removed_ratio = tally.removed as f64 / tally.total as f64;
+            let cluster_penalty = if clustered { config.cluster_penalty.min(1.0) } else { 0.0 };
+            let suspicion = (1.0 - (1.0 - removed_ratio) * (1.0 - cluster_penalty)).clamp(0.0, 1.0);
+
+            PostSuspicion {
+                post_id,
+                total_views: tally.total,
+                removed_views: tally.removed,
+                suspicion,
+                reasons: tally.reasons.into_iter().collect(),
+            }
+        })
+        .collect()
+}
+
+/// 集計中の投稿
+#[derive(Default)]
+struct PostTally {
+    total: u32,
+    removed: u32,
+    reasons: BTreeSet<AbuseReason>,
+    flags: HashMap<u8, u32>,
+}
@@ -0,0 +1,185 @@
+//! 複数投稿のスコアを一括計算するバッチAPI
+//!
+//! 投稿ごとに計算機を作りJSONを往復させる代わりに、
+//! 全投稿の入力を一度に受け取りスコアと上位N件のランキングを返す。
+
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::clock::TimeSkewPolicy;
+use crate::config::ScoringConfig;
+use crate::engagement::EngagementCounts;
+use crate::error::TrendError;
+use crate::period::Period;
+use crate::ranking::{rank_top_n, RankedPost, RankingKey};
+use crate::trend_calculator::{
+    DirectCalculationData, RedisHllData, TrendCalculator, TrendStats, TrendingResult,
+};
+
+/// 投稿ごとの計算入力データ
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchInputData {
+    /// 直接計算用データ
+    Direct(DirectCalculationData),
+    /// Redis HLLデータ
+    Hll(RedisHllData),
+}
+
+/// バッチ計算の1投稿分の入力
+///
+/// JSONでは `{"post_id": 1, "direct": {...}}` または `{"post_id": 1, "hll": {...}}`。
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BatchPostInput {
+    pub post_id: u32,
+    #[serde(flatten)]
+    pub data: BatchInputData,
+    #[serde(default)]
+    pub suspicion: f64, // AbuseDetectorで求めた疑わしさ（0〜1、スコアを割り引く）
+}
+
+impl BatchPostInput {
+    /// 閲覧数と行動の件数（`EngagementRates::from_posts`でサイト全体の率を求める際に使う）
+    pub fn engagement_counts(&self) -> EngagementCounts {
+        match &self.data {
+            BatchInputData::Direct(data) => EngagementCounts {
+                views: data.view_increase,
+                likes: data.like_increase,
+                comments: data.comment_increase,
+                bookmarks: data.bookmark_count,
+            },
+            BatchInputData::Hll(data) => EngagementCounts::from(data),
+        }
+    }
+}
+
+/// 計算方式ごとのスコア内訳
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchComponents {
+    Direct(TrendingResult),
+    Hll(TrendStats),
+}
+
+/// 1投稿分の計算結果
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BatchScore {
+    pub post_id: u32,
+    pub score: f64,
+    pub components: BatchComponents,
+}
+
+/// 計算に失敗した投稿
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct BatchError {
+    pub post_id: u32,
+    pub error: TrendError,
+}
+
+/// バッチ計算の結果
+#[derive(Serialize, Debug, Clone, PartialEq, Default)]
+pub struct BatchResult {
+    /// 入力順の計算結果（失敗した投稿は含まない）
+    pub scores: Vec<BatchScore>,
+    /// スコア降順の上位N件
+    pub ranking: Vec<RankedPost>,
+    /// 計算に失敗した投稿
+    pub errors: Vec<BatchError>,
+}
+
+/// 複数投稿のスコアを一括計算する
+pub struct BatchScorer {
+    period: Period,
+    top_n: usize,
+    skew_policy: TimeSkewPolicy,
+    config: Arc<ScoringConfig>,
+}
+
+impl BatchScorer {
+    /// 集計期間と上位件数を指定してバッチ計算機を作成
+    pub fn new(period: Period, top_n: usize) -> Self {
+        BatchScorer {
+            period,
+            top_n,
+            skew_policy: TimeSkewPolicy::default(),
+            config: Arc::new(ScoringConfig::default()),
+        }
+    }
+
+    /// 未来のタイムスタンプの扱いを差し替える
+    pub fn with_skew_policy(mut self, policy: TimeSkewPolicy) -> Self {
+        self.skew_policy = policy;
+        self
+    }
+
+    /// スコア計算の設定を差し替える
+    pub fn with_config(mut self, config: Arc<ScoringConfig>) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// 指定時刻（UNIXミリ秒）を現在時刻として全投稿を計算しランキングを作成
+    ///
+    /// 個別の投稿の計算エラーはバッチ全体を止めず`errors`に記録する。
+    /// スコアがNaNや無限大になった投稿も、ランキングに入れず`errors`に記録する。
+    pub fn score_at(&self, inputs: &[BatchPostInput], now: u64) -> BatchResult {
+        let mut calculator = TrendCalculator::new(0, self.period)
+            .with_skew_policy(self.skew_policy)
+            .with_config(Arc::clone(&self.config));
+        let mut result = BatchResult {
+            scores: Vec::with_capacity(inputs.len()),
+            ..BatchResult::default()
+        };
+        let mut keys = Vec::with_capacity(inputs.len());
+
+        for input in inputs {
+            calculator.set_post_id(input.post_id);
+            calculator.set_suspicion(input.suspicion);
+
+            // 同点時の順位決定に使うユニークユーザー数と最終アクティビティ時刻
+            let (unique_users, last_activity) = match &input.data {
+                BatchInputData::Direct(data) => (data.unique_users, data.last_updated),
+                BatchInputData::Hll(data) => (data.unique_users, data.last_activity_time),
+            };
+
+            let scored = match &input.data {
+                BatchInputData::Direct(data) => calculator
+                    .calculate_trending_score_direct_at(data, now)
+                    .map(|r| (r.score, BatchComponents::Direct(r))),
+                BatchInputData::Hll(data) => calculator
+                    .calculate_with_redis_hll_data_at(data, now)
+                    .map(|s| (s.score, BatchComponents::Hll(s))),
+            }
+            .and_then(|(score, components)| {
+                if score.is_finite() {
+                    Ok((score, components))
+                } else {
+                    Err(TrendError::NonFiniteScore { score })
+                }
+            });
+
+            match scored {
+                Ok((score, components)) => {
+                    keys.push(RankingKey {
+                        post_id: input.post_id,
+                        score,
+                        unique_users,
+                        last_activity,
+                    });
+                    result.scores.push(BatchScore {
+                        post_id: input.post_id,
+                        score,
+                        components,
+                    });
+                }
+                Err(error) => result.errors.push(BatchError {
+                    post_id: input.post_id,
+                    error,
+                }),
+            }
+        }
+
+        result.ranking = rank_top_n(keys, self.top_n);
+        result
+    }
+}
@@ -0,0 +1,153 @@
+//! スコア設定の変更によるランキングの変化を確認するコマンド
+//!
+//! ```text
+//! cargo run --bin replay -- snapshot.jsonl --candidate new.json [--baseline old.json]
+//!     [--period daily] [--top 100] [--movers 20] [--now <UNIXミリ秒>] [--json]
+//! ```
+//!
+//! `--baseline`を省略した場合は既定の設定と比べる。`--now`を省略した場合はシステム時刻を使う。
+//! 変更後の設定が変更前と同じ場合は、比較しても意味がないためエラーで終了する。
+
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::{env, fs};
+
+use trend_calculator::{
+    compare_rankings, parse_snapshot, replay_snapshot, Clock, Period, RankChange, ReplayReport, ScoringConfig,
+    SystemClock,
+};
+
+const USAGE: &str = "使い方: replay <スナップショット.jsonl> --candidate <設定.json> [--baseline <設定.json>] \
+[--period daily] [--top 100] [--movers 20] [--now <UNIXミリ秒>] [--json]";
+
+/// コマンドライン引数
+struct Options {
+    snapshot: String,
+    baseline: Option<String>,
+    candidate: String,
+    period: Period,
+    top_n: usize,
+    movers: usize,
+    now: Option<u64>,
+    json: bool,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut snapshot = None;
+    let mut baseline = None;
+    let mut candidate = None;
+    let mut period = Period::Daily;
+    let mut top_n = 100;
+    let mut movers = 20;
+    let mut now = None;
+    let mut json = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().cloned().ok_or_else(|| format!("{}の値がありません", name));
+        match arg.as_str() {
+            "--baseline" => baseline = Some(value(arg)?),
+            "--candidate" => candidate = Some(value(arg)?),
+            "--period" => period = value(arg)?.parse().map_err(|e| format!("{}", e))?,
+            "--top" => top_n = value(arg)?.parse().map_err(|e| format!("--topの値が不正です: {}", e))?,
+            "--movers" => movers = value(arg)?.parse().map_err(|e| format!("--moversの値が不正です: {}", e))?,
+            "--now" => now = Some(value(arg)?.parse().map_err(|e| format!("--nowの値が不正です: {}", e))?),
+            "--json" => json = true,
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ if arg.starts_with("--") => return Err(format!("未知のオプションです: {}", arg)),
+            _ if snapshot.is_none() => snapshot = Some(arg.clone()),
+            _ => return Err(format!("余分な引数です: {}", arg)),
+        }
+    }
+
+    Ok(Options {
+        snapshot: snapshot.ok_or("スナップショットのファイルを指定してください")?,
+        baseline,
+        candidate: candidate.ok_or("--candidateで比較する設定を指定してください")?,
+        period,
+        top_n,
+        movers,
+        now,
+        json,
+    })
+}
+
+fn read(path: &str) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|e| format!("{}を読み込めません: {}", path, e))
+}
+
+/// 設定を読み込む（未知のフィールドは綴り誤りとしてエラーにする）
+fn load_config(path: Option<&str>) -> Result<Arc<ScoringConfig>, String> {
+    match path {
+        Some(path) => ScoringConfig::from_json(&read(path)?)
+            .map(Arc::new)
+            .map_err(|e| format!("{}: {}", path, e)),
+        None => Ok(Arc::new(ScoringConfig::default())),
+    }
+}
+
+fn format_rank(rank: Option<u32>) -> String {
+    rank.map_or("-".to_string(), |rank| rank.to_string())
+}
+
+fn format_change(change: &RankChange) -> String {
+    format!(
+        "  #{:<8} {:>6} → {:<6} ({:+})  score {} → {}",
+        change.post_id,
+        format_rank(change.baseline_rank),
+        format_rank(change.candidate_rank),
+        change.rank_change,
+        change.baseline_score.map_or("-".to_string(), |s| format!("{:.2}", s)),
+        change.candidate_score.map_or("-".to_string(), |s| format!("{:.2}", s)),
+    )
+}
+
+fn print_report(report: &ReplayReport) {
+    println!("比較した投稿数: {}", report.compared_posts);
+    println!("計算エラー: 変更前 {} 件 / 変更後 {} 件", report.baseline_errors, report.candidate_errors);
+    println!("ケンドールのτ: {:.4}", report.kendall_tau);
+    println!("スピアマンのρ: {:.4}", report.spearman_rho);
+    println!("上位{}件の重なり: {}件", report.top_n, report.top_n_overlap);
+
+    for (title, changes) in [
+        ("上位に入った投稿", &report.entered),
+        ("上位から外れた投稿", &report.exited),
+        ("順位の変動が大きい投稿", &report.movers),
+    ] {
+        println!();
+        println!("{} ({}件)", title, changes.len());
+        changes.iter().for_each(|change| println!("{}", format_change(change)));
+    }
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let posts = parse_snapshot(&read(&options.snapshot)?).map_err(|e| e.to_string())?;
+    let baseline_config = load_config(options.baseline.as_deref())?;
+    let candidate_config = load_config(Some(&options.candidate))?;
+    if candidate_config == baseline_config {
+        return Err(format!("{}: 変更後の設定が変更前の設定と同じです", options.candidate));
+    }
+    let now = options.now.unwrap_or_else(|| SystemClock.now_ms());
+
+    let baseline = replay_snapshot(&posts, baseline_config, options.period, now);
+    let candidate = replay_snapshot(&posts, candidate_config, options.period, now);
+    let report = compare_rankings(&baseline, &candidate, options.top_n, options.movers);
+
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?);
+    } else {
+        print_report(&report);
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match parse_args(&args).and_then(|options| run(&options)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("{}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
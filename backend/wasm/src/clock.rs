@@ -0,0 +1,80 @@
+//! 計算で使用する現在時刻の取得元
+//!
+//! スコア計算を再現可能にするため、現在時刻は`Clock`経由で注入する。
+//! 過去時点のランキングを再計算する場合は`FixedClock`を使う。
+
+use crate::error::{Result, TrendError};
+
+/// 現在時刻（UNIXミリ秒）を返す時計
+pub trait Clock {
+    /// 現在時刻（UNIXミリ秒）
+    fn now_ms(&self) -> u64;
+}
+
+/// 実行環境のシステム時刻を返す時計
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_ms(&self) -> u64 {
+        #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+        {
+            js_sys::Date::now() as u64
+        }
+        #[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+        {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0)
+        }
+    }
+}
+
+/// 常に同じ時刻を返す時計（バックフィル・再計算用）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedClock(pub u64);
+
+impl Clock for FixedClock {
+    fn now_ms(&self) -> u64 {
+        self.0
+    }
+}
+
+/// 現在時刻より未来のタイムスタンプ（クライアントの時計ずれ等）の扱い
+///
+/// - `Clamp`: 経過時間を0として扱う（未来の活動は「今」起きたとみなす）
+/// - `Reject`: `TrendError::FutureTimestamp`を返して計算を中止する
+///
+/// 既定は`Clamp`。1件のずれたデータでランキング全体の計算が止まらないようにする。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeSkewPolicy {
+    #[default]
+    Clamp,
+    Reject,
+}
+
+impl TimeSkewPolicy {
+    /// 基準時刻から現在時刻までの経過時間（ミリ秒）をポリシーに従って計算
+    pub fn elapsed_since(self, timestamp: u64, now: u64) -> Result<u64> {
+        match now.checked_sub(timestamp) {
+            Some(elapsed) => Ok(elapsed),
+            None => match self {
+                TimeSkewPolicy::Clamp => Ok(0),
+                TimeSkewPolicy::Reject => Err(TrendError::FutureTimestamp { timestamp, now }),
+            },
+        }
+    }
+}
+
+impl std::str::FromStr for TimeSkewPolicy {
+    type Err = TrendError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "clamp" => Ok(TimeSkewPolicy::Clamp),
+            "reject" => Ok(TimeSkewPolicy::Reject),
+            _ => Err(TrendError::parse("時刻ずれポリシー", format!("未知の値です: {}", s))),
+        }
+    }
+}
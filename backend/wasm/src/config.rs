@@ -0,0 +1,593 @@
+//! スコア計算の重み・係数の設定
+//!
+//! 既定値はすべて従来のハードコードされた定数と同じ。
+//! JSONから一度読み込み、`Arc<ScoringConfig>`として全計算機で共有する。
+//! `ScoringConfig::from_json`では、JSONを既定値の上にフィールド単位で重ねるため、
+//! 期間ごとの表（`daily`・`weekly`など）の一部も含め、変更したい値だけを記述すればよい。
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::engagement::EngagementRates;
+use crate::error::{Result, TrendError};
+use crate::period::Period;
+
+/// 期間ごとの値（日次・週次・月次・年次）
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct PeriodValues<T> {
+    pub daily: T,
+    pub weekly: T,
+    pub monthly: T,
+    pub yearly: T,
+}
+
+impl<T: Copy> PeriodValues<T> {
+    /// 期間に対応する値を取得（任意の期間は`Period::base`の標準期間の値）
+    pub fn get(&self, period: Period) -> T {
+        match period.base() {
+            Period::Weekly => self.weekly,
+            Period::Monthly => self.monthly,
+            Period::Yearly => self.yearly,
+            _ => self.daily,
+        }
+    }
+
+    fn values(&self) -> [T; 4] {
+        [self.daily, self.weekly, self.monthly, self.yearly]
+    }
+}
+
+/// 行動（閲覧・いいね・本棚追加・コメント）ごとの重み
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct ActionWeights {
+    pub view: f64,
+    pub like: f64,
+    pub bookmark: f64,
+    pub comment: f64,
+}
+
+impl Default for ActionWeights {
+    fn default() -> Self {
+        ActionWeights {
+            view: 1.0,
+            like: 3.0,
+            bookmark: 5.0,
+            comment: 2.0,
+        }
+    }
+}
+
+/// エンゲージメントイベント（いいね・コメント・本棚追加）の重み
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct EngagementWeights {
+    pub like: f64,
+    pub comment: f64,
+    pub bookmark: f64,
+}
+
+impl Default for EngagementWeights {
+    fn default() -> Self {
+        EngagementWeights {
+            like: 2.0,
+            comment: 3.0,
+            bookmark: 5.0,
+        }
+    }
+}
+
+/// ベーススコアを構成する要素ごとの重み
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ComponentWeights {
+    pub views: f64,        // 閲覧数スコア
+    pub unique_users: f64, // ユニークユーザースコア
+    pub growth: f64,       // 成長率
+    pub activity: f64,     // モメンタムとエンゲージメント
+}
+
+/// 正規化した各要素をスコアの単位に揃える倍率
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct ComponentScales {
+    pub view_score: f64,            // 閲覧数 → 閲覧数スコア
+    pub growth: f64,                // 正規化成長率
+    pub momentum: f64,              // 正規化モメンタム
+    pub engagement: f64,            // 正規化エンゲージメント
+    pub engagement_normalize: f64,  // エンゲージメント → 正規化エンゲージメント
+}
+
+impl Default for ComponentScales {
+    fn default() -> Self {
+        ComponentScales {
+            view_score: 0.1,
+            growth: 100.0,
+            momentum: 50.0,
+            engagement: 50.0,
+            engagement_normalize: 3.0,
+        }
+    }
+}
+
+/// 期間ごとの要素の重み（既定値）
+fn default_component_weights() -> PeriodValues<ComponentWeights> {
+    let weights = |views, unique_users, growth, activity| ComponentWeights {
+        views,
+        unique_users,
+        growth,
+        activity,
+    };
+    PeriodValues {
+        daily: weights(0.4, 0.3, 0.2, 0.1),
+        weekly: weights(0.3, 0.3, 0.3, 0.1),
+        monthly: weights(0.2, 0.3, 0.4, 0.1),
+        yearly: weights(0.1, 0.3, 0.5, 0.1),
+    }
+}
+
+/// 直接計算（calculate_trending_score_direct）の設定
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct DirectScoringConfig {
+    pub action_weights: ActionWeights,
+    pub decay_rates: PeriodValues<f64>,       // 1時間あたりの減衰率
+    pub momentum_weights: PeriodValues<f64>,
+    pub momentum_cap: f64,
+    pub min_previous_rate: f64,               // 前回増加率の下限（ゼロ除算防止）
+    pub diversity_weights: PeriodValues<f64>,
+}
+
+impl Default for DirectScoringConfig {
+    fn default() -> Self {
+        DirectScoringConfig {
+            action_weights: ActionWeights::default(),
+            decay_rates: PeriodValues { daily: 0.1, weekly: 0.05, monthly: 0.02, yearly: 0.005 },
+            momentum_weights: PeriodValues { daily: 2.0, weekly: 1.5, monthly: 1.0, yearly: 0.5 },
+            momentum_cap: 5.0,
+            min_previous_rate: 0.01,
+            diversity_weights: PeriodValues { daily: 1.5, weekly: 1.8, monthly: 2.0, yearly: 2.5 },
+        }
+    }
+}
+
+/// Redis HLLデータからの計算（calculate_with_redis_hll_data）の設定
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct HllScoringConfig {
+    pub engagement_weights: EngagementWeights,
+    pub component_weights: PeriodValues<ComponentWeights>,
+    pub momentum_scale: f64,                  // log10(時間あたり閲覧数)の倍率
+    pub momentum_cap: f64,
+    pub momentum_floor: Option<f64>,          // モメンタムの下限（未指定なら下限なし）
+    pub decay_rates: PeriodValues<f64>,       // 1時間あたりの減衰率
+}
+
+impl Default for HllScoringConfig {
+    fn default() -> Self {
+        HllScoringConfig {
+            engagement_weights: EngagementWeights::default(),
+            component_weights: default_component_weights(),
+            momentum_scale: 0.5,
+            momentum_cap: 2.0,
+            momentum_floor: None,
+            decay_rates: PeriodValues { daily: 0.1, weekly: 0.05, monthly: 0.02, yearly: 0.005 },
+        }
+    }
+}
+
+/// 時間窓とイベントからの計算（calculate_trend_score）の設定
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct TrendScoringConfig {
+    pub engagement_weights: EngagementWeights,
+    pub quality_weights: EngagementWeights,   // 高品質エンゲージメント係数の重み
+    pub quality_scale: f64,
+    pub component_weights: PeriodValues<ComponentWeights>,
+    pub decay_rates: PeriodValues<f64>,       // 1時間あたりの減衰率
+    pub freshness_boost: f64,                 // 鮮度ブーストの最大値
+    pub growth_compare_hours: PeriodValues<u64>, // 成長率の比較に使う時間数
+    pub growth: GrowthConfig,                 // 成長率・モメンタムの推定方法
+}
+
+impl Default for TrendScoringConfig {
+    fn default() -> Self {
+        TrendScoringConfig {
+            engagement_weights: EngagementWeights::default(),
+            quality_weights: EngagementWeights {
+                like: 1.0,
+                comment: 2.0,
+                bookmark: 3.0,
+            },
+            quality_scale: 0.5,
+            component_weights: default_component_weights(),
+            decay_rates: PeriodValues { daily: 0.1, weekly: 0.05, monthly: 0.02, yearly: 0.01 },
+            freshness_boost: 0.5,
+            growth_compare_hours: PeriodValues { daily: 4, weekly: 24, monthly: 72, yearly: 168 },
+            growth: GrowthConfig::default(),
+        }
+    }
+}
+
+/// 成長率・モメンタムの推定方法
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GrowthEstimator {
+    #[default]
+    BlockCompare, // 直近N時間とその前のN時間の比較（従来の方法）
+    Ewma,         // 短期・長期の指数移動平均の比較
+    LogLinear,    // 対数線形回帰の傾き
+}
+
+/// 成長率・モメンタムの推定（`growth`モジュール）の設定
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct GrowthConfig {
+    pub estimator: GrowthEstimator,
+    pub count_prior: f64,          // 各時間の閲覧数に加える事前カウント（閲覧の少ない投稿の値を安定させる）
+    pub fast_half_life_ratio: f64, // 短期の指数移動平均の半減期（比較する時間数に対する比）
+    pub slow_half_life_ratio: f64, // 長期の指数移動平均の半減期（比較する時間数に対する比）
+}
+
+impl Default for GrowthConfig {
+    fn default() -> Self {
+        GrowthConfig {
+            estimator: GrowthEstimator::default(),
+            count_prior: 1.0,
+            fast_half_life_ratio: 0.5,
+            slow_half_life_ratio: 2.0,
+        }
+    }
+}
+
+/// シリーズの計算（SeriesTrendCalculator）の設定
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct SeriesScoringConfig {
+    pub episode_weight: f64,                  // エピソード平均スコアの重み
+    pub follower_weight: f64,                 // フォロワー増加数の重み
+    pub decay_rates: PeriodValues<f64>,       // 1時間あたりの減衰率
+    pub momentum_weights: PeriodValues<f64>,
+    pub momentum_cap: f64,
+    pub min_previous_rate: f64,               // 前回フォロワー増加率がない場合の値
+    pub episode_bonus_scale: f64,             // log10(エピソード数+1)の倍率
+}
+
+impl Default for SeriesScoringConfig {
+    fn default() -> Self {
+        SeriesScoringConfig {
+            episode_weight: 3.0,
+            follower_weight: 7.0,
+            decay_rates: PeriodValues { daily: 0.1, weekly: 0.05, monthly: 0.02, yearly: 0.005 },
+            momentum_weights: PeriodValues { daily: 3.0, weekly: 2.5, monthly: 1.5, yearly: 1.0 },
+            momentum_cap: 5.0,
+            min_previous_rate: 0.01,
+            episode_bonus_scale: 0.1,
+        }
+    }
+}
+
+/// 総合ランキング（TotalRankCalculator）の設定
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct TotalScoringConfig {
+    pub post_weights: ActionWeights,          // viewはユニーク閲覧者数に掛ける
+    pub unique_user_step: f64,                // ユニーク閲覧者1人あたりの係数の増分
+    pub unique_user_factor_cap: f64,          // ユニークユーザー係数の上限
+    pub follower_log_scale: f64,              // log10(フォロワー数+1)の倍率
+    pub follower_weight: f64,
+    pub episode_weight: f64,
+    pub default_episode_score: f64,           // 平均スコアがない場合のエピソード1件あたりのスコア
+    pub completed_bonus: f64,                 // 完結済みシリーズの倍率
+    pub min_series_score: f64,                // シリーズスコアの下限
+}
+
+impl Default for TotalScoringConfig {
+    fn default() -> Self {
+        TotalScoringConfig {
+            post_weights: ActionWeights {
+                view: 0.1,
+                like: 1.0,
+                bookmark: 2.0,
+                comment: 2.5,
+            },
+            unique_user_step: 0.005,
+            unique_user_factor_cap: 2.0,
+            follower_log_scale: 10.0,
+            follower_weight: 0.6,
+            episode_weight: 0.4,
+            default_episode_score: 2.0,
+            completed_bonus: 1.1,
+            min_series_score: 0.01,
+        }
+    }
+}
+
+/// 代替のランキングモデル（`scorer`モジュールのHN・Reddit・指数減衰）の設定
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct HotScoringConfig {
+    pub point_weights: ActionWeights,       // 閲覧・行動をポイントに換算する重み
+    pub gravity: f64,                       // HNの(p - 1) / (t + 2)^gの指数g
+    pub reddit_epoch_secs: u64,             // Redditのhotの基準時刻（UNIX秒）
+    pub reddit_time_divisor: f64,           // 何秒でlog10(ポイント)の1に相当するか
+    pub half_life_hours: PeriodValues<f64>, // 指数減衰の半減期（時間）
+}
+
+impl Default for HotScoringConfig {
+    fn default() -> Self {
+        HotScoringConfig {
+            point_weights: ActionWeights::default(),
+            gravity: 1.8,
+            reddit_epoch_secs: 1_134_028_003,
+            reddit_time_divisor: 45_000.0,
+            half_life_hours: PeriodValues { daily: 6.0, weekly: 36.0, monthly: 120.0, yearly: 720.0 },
+        }
+    }
+}
+
+/// エンゲージメント率の求め方
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum EngagementModel {
+    #[default]
+    Raw,              // 件数 / 閲覧数（従来の方法）
+    BayesianAverage,  // サイト全体の率を事前分布とするベイズ平均
+    WilsonLowerBound, // ベイズ平均の率のWilsonスコア信頼区間の下限
+}
+
+/// エンゲージメント率の平滑化（`engagement`モジュール）の設定
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct EngagementConfig {
+    pub model: EngagementModel,
+    pub prior: EngagementRates, // サイト全体の閲覧1回あたりの率（EngagementRates::from_postsで求める）
+    pub prior_views: f64,       // 事前分布を何回分の閲覧として扱うか
+    pub wilson_z: f64,          // Wilsonスコアの信頼区間のz値
+}
+
+impl Default for EngagementConfig {
+    fn default() -> Self {
+        EngagementConfig {
+            model: EngagementModel::default(),
+            prior: EngagementRates::default(),
+            prior_views: 50.0,
+            wilson_z: 1.96,
+        }
+    }
+}
+
+/// 不正閲覧の検出（AbuseDetector）の設定
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct AbuseConfig {
+    pub burst_window_ms: u64,     // 連続閲覧を数える期間
+    pub max_views_per_burst: u32, // 期間内に1ユーザーが1投稿を閲覧できる上限（超過分は除外）
+    pub uniform_min_views: u32,   // 閲覧間隔の均一さを判定する最小閲覧数
+    pub uniform_max_cv: f64,      // 閲覧間隔の変動係数がこれ以下なら機械的とみなす
+    pub spree_window_ms: u64,     // 短時間の多投稿閲覧を数える期間
+    pub max_posts_per_spree: u32, // 期間内に閲覧できる投稿数の上限
+    pub cluster_min_views: u32,   // デバイス・国の偏りを判定する最小閲覧数
+    pub cluster_min_share: f64,   // 1つのデバイス・国の組み合わせが占める割合の閾値
+    pub cluster_min_excess: f64,  // サイト全体の割合をどれだけ上回れば偏りとみなすか
+    pub cluster_penalty: f64,     // 偏りがある投稿の疑わしさ
+    pub suspicion_discount: f64,  // 疑わしさ1.0の投稿のスコアを割り引く割合
+}
+
+impl Default for AbuseConfig {
+    fn default() -> Self {
+        AbuseConfig {
+            burst_window_ms: 60 * 60 * 1000,
+            max_views_per_burst: 30,
+            uniform_min_views: 6,
+            uniform_max_cv: 0.05,
+            spree_window_ms: 10 * 1000,
+            max_posts_per_spree: 5,
+            cluster_min_views: 50,
+            cluster_min_share: 0.95,
+            cluster_min_excess: 0.3,
+            cluster_penalty: 0.5,
+            suspicion_discount: 1.0,
+        }
+    }
+}
+
+/// スコア計算の設定一式
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct ScoringConfig {
+    pub scales: ComponentScales,
+    pub direct: DirectScoringConfig,
+    pub hll: HllScoringConfig,
+    pub trend: TrendScoringConfig,
+    pub series: SeriesScoringConfig,
+    pub total: TotalScoringConfig,
+    pub abuse: AbuseConfig,
+    pub engagement: EngagementConfig,
+    pub hot: HotScoringConfig,
+}
+
+impl ScoringConfig {
+    /// JSONから設定を読み込み、値を検証する
+    ///
+    /// JSONに書かれたフィールドだけを既定値に上書きする（オブジェクトは入れ子まで再帰的に重ねる）。
+    /// 既定値にないフィールドはエラーにする。
+    pub fn from_json(json: &str) -> Result<Self> {
+        let overrides: Value = serde_json::from_str(json).map_err(|e| TrendError::parse("スコア設定", e))?;
+        if !overrides.is_object() {
+            return Err(TrendError::parse("スコア設定", "JSONオブジェクトで指定してください"));
+        }
+        let mut merged = serde_json::to_value(ScoringConfig::default()).map_err(|e| TrendError::parse("スコア設定", e))?;
+        overlay(&mut merged, overrides, "")?;
+        let config: ScoringConfig = serde_json::from_value(merged).map_err(|e| TrendError::parse("スコア設定", e))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// すべての重み・係数が有限かつ非負であることを確認
+    pub fn validate(&self) -> Result<()> {
+        let component_weights = |table: &PeriodValues<ComponentWeights>| {
+            table
+                .values()
+                .into_iter()
+                .flat_map(|w| [w.views, w.unique_users, w.growth, w.activity])
+                .collect::<Vec<_>>()
+        };
+        let engagement = |w: &EngagementWeights| [w.like, w.comment, w.bookmark];
+
+        let values = vec![
+            ("scales", vec![
+                self.scales.view_score,
+                self.scales.growth,
+                self.scales.momentum,
+                self.scales.engagement,
+                self.scales.engagement_normalize,
+            ]),
+            ("direct.action_weights", vec![
+                self.direct.action_weights.view,
+                self.direct.action_weights.like,
+                self.direct.action_weights.bookmark,
+                self.direct.action_weights.comment,
+            ]),
+            ("direct.decay_rates", self.direct.decay_rates.values().to_vec()),
+            ("direct.momentum_weights", self.direct.momentum_weights.values().to_vec()),
+            ("direct.momentum_cap", vec![self.direct.momentum_cap]),
+            ("direct.diversity_weights", self.direct.diversity_weights.values().to_vec()),
+            ("hll.engagement_weights", engagement(&self.hll.engagement_weights).to_vec()),
+            ("hll.component_weights", component_weights(&self.hll.component_weights)),
+            ("hll.momentum_scale", vec![self.hll.momentum_scale]),
+            ("hll.momentum_cap", vec![self.hll.momentum_cap]),
+            ("hll.decay_rates", self.hll.decay_rates.values().to_vec()),
+            ("trend.engagement_weights", engagement(&self.trend.engagement_weights).to_vec()),
+            ("trend.quality_weights", engagement(&self.trend.quality_weights).to_vec()),
+            ("trend.quality_scale", vec![self.trend.quality_scale]),
+            ("trend.component_weights", component_weights(&self.trend.component_weights)),
+            ("trend.decay_rates", self.trend.decay_rates.values().to_vec()),
+            ("trend.freshness_boost", vec![self.trend.freshness_boost]),
+            ("series", vec![
+                self.series.episode_weight,
+                self.series.follower_weight,
+                self.series.momentum_cap,
+                self.series.episode_bonus_scale,
+            ]),
+            ("series.decay_rates", self.series.decay_rates.values().to_vec()),
+            ("series.momentum_weights", self.series.momentum_weights.values().to_vec()),
+            ("total.post_weights", vec![
+                self.total.post_weights.view,
+                self.total.post_weights.like,
+                self.total.post_weights.bookmark,
+                self.total.post_weights.comment,
+            ]),
+            ("total", vec![
+                self.total.unique_user_step,
+                self.total.unique_user_factor_cap,
+                self.total.follower_log_scale,
+                self.total.follower_weight,
+                self.total.episode_weight,
+                self.total.default_episode_score,
+                self.total.completed_bonus,
+                self.total.min_series_score,
+            ]),
+            ("abuse", vec![
+                self.abuse.uniform_max_cv,
+                self.abuse.cluster_min_share,
+                self.abuse.cluster_min_excess,
+                self.abuse.cluster_penalty,
+                self.abuse.suspicion_discount,
+            ]),
+            ("hot.point_weights", vec![
+                self.hot.point_weights.view,
+                self.hot.point_weights.like,
+                self.hot.point_weights.bookmark,
+                self.hot.point_weights.comment,
+            ]),
+            ("hot.gravity", vec![self.hot.gravity]),
+            ("engagement", vec![
+                self.engagement.prior.like,
+                self.engagement.prior.comment,
+                self.engagement.prior.bookmark,
+                self.engagement.prior_views,
+                self.engagement.wilson_z,
+            ]),
+        ];
+
+        for (name, list) in values {
+            if list.iter().any(|v| !v.is_finite() || *v < 0.0) {
+                return Err(TrendError::parse(
+                    "スコア設定",
+                    format!("{}には有限の非負の値を指定してください", name),
+                ));
+            }
+        }
+
+        // 前回増加率の下限・事前カウント・半減期・除数は0だとゼロ除算や対数の発散になるため正の値のみ許可
+        for (name, min_rate) in [
+            ("direct.min_previous_rate", self.direct.min_previous_rate),
+            ("series.min_previous_rate", self.series.min_previous_rate),
+            ("trend.growth.count_prior", self.trend.growth.count_prior),
+            ("trend.growth.fast_half_life_ratio", self.trend.growth.fast_half_life_ratio),
+            ("trend.growth.slow_half_life_ratio", self.trend.growth.slow_half_life_ratio),
+            ("hot.reddit_time_divisor", self.hot.reddit_time_divisor),
+            ("hot.half_life_hours.daily", self.hot.half_life_hours.daily),
+            ("hot.half_life_hours.weekly", self.hot.half_life_hours.weekly),
+            ("hot.half_life_hours.monthly", self.hot.half_life_hours.monthly),
+            ("hot.half_life_hours.yearly", self.hot.half_life_hours.yearly),
+        ] {
+            if !min_rate.is_finite() || min_rate <= 0.0 {
+                return Err(TrendError::parse(
+                    "スコア設定",
+                    format!("{}には正の値を指定してください", name),
+                ));
+            }
+        }
+
+        // モメンタムの下限は負の値も取るため、有限かつ上限以下であることだけを確認
+        if let Some(floor) = self.hll.momentum_floor {
+            if !floor.is_finite() || floor > self.hll.momentum_cap {
+                return Err(TrendError::parse(
+                    "スコア設定",
+                    "hll.momentum_floorにはhll.momentum_cap以下の有限の値を指定してください",
+                ));
+            }
+        }
+
+        // 期間0の窓は判定の区間が空になり、上限0は全閲覧を除外するため範囲を決めて許可
+        const YEAR_MS: u64 = 365 * 24 * 60 * 60 * 1000;
+        for (name, value, min, max) in [
+            ("abuse.burst_window_ms", self.abuse.burst_window_ms, 1, YEAR_MS),
+            ("abuse.spree_window_ms", self.abuse.spree_window_ms, 1, YEAR_MS),
+            ("abuse.max_views_per_burst", self.abuse.max_views_per_burst as u64, 1, u32::MAX as u64),
+            ("abuse.max_posts_per_spree", self.abuse.max_posts_per_spree as u64, 1, u32::MAX as u64),
+            // 間隔の変動係数は閲覧3件（間隔2つ）以上でないと求まらない
+            ("abuse.uniform_min_views", self.abuse.uniform_min_views as u64, 3, u32::MAX as u64),
+        ] {
+            if !(min..=max).contains(&value) {
+                return Err(TrendError::parse(
+                    "スコア設定",
+                    format!("{}には{}以上{}以下の値を指定してください", name, min, max),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `overrides`のフィールドを`base`に重ねる（両方がオブジェクトの場合のみ再帰し、それ以外は置き換える）
+///
+/// `base`にないキーは綴り誤りとみなしてエラーにする（`path`はエラーメッセージに使う親のキー）。
+fn overlay(base: &mut Value, overrides: Value, path: &str) -> Result<()> {
+    match (base, overrides) {
+        (Value::Object(base), Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                let key_path = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                match base.get_mut(&key) {
+                    Some(slot) => overlay(slot, value, &key_path)?,
+                    None => {
+                        return Err(TrendError::parse("スコア設定", format!("未知の設定項目です: {}", key_path)));
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+    Ok(())
+}
@@ -0,0 +1,285 @@
+//! Bloomフィルターによる重複閲覧の除外
+//!
+//! correctedViewTrackingService.js の`SimpleBloomFilter`は10分ごとに全消去されるため、
+//! 消去直後は同じ読者の閲覧もすべて新規として数えてしまう。
+//! ここでは世代を2つ持つBloomフィルターを使い、(投稿, ユーザー)ごとに
+//! 少なくとも`window_ms`の間は重複として扱う（最大で`window_ms`の2倍）。
+//! 状態はスナップショットとして保存・復元でき、再起動をまたいで重複判定を引き継げる。
+
+use serde::{Deserialize, Serialize};
+
+use crate::clock::{Clock, SystemClock};
+use crate::error::{Result, TrendError};
+use crate::hyperloglog::murmur_hash64a;
+
+const SNAPSHOT_MAGIC: &[u8; 4] = b"VDDP";
+const SNAPSHOT_VERSION: u8 = 1;
+const SNAPSHOT_HEADER_SIZE: usize = 72;
+const HASH_SEED_1: u64 = 0x9747_b28c;
+const HASH_SEED_2: u64 = 0x85eb_ca6b;
+/// 世代がまだ始まっていないことを表すスナップショット上の値
+const NOT_STARTED: u64 = u64::MAX;
+/// 1世代のフィルターの最大ビット数（512MiB）
+const MAX_FILTER_BITS: u64 = 1 << 32;
+
+/// 重複除外の設定
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct DedupConfig {
+    pub expected_views: u64,      // 1世代（window_ms）あたりに想定する新規閲覧数
+    pub false_positive_rate: f64, // 想定件数での偽陽性率（新規閲覧を重複と誤判定する確率）
+    pub window_ms: u64,           // (投稿, ユーザー)ごとの重複判定の期間
+}
+
+impl Default for DedupConfig {
+    fn default() -> Self {
+        DedupConfig {
+            expected_views: 100_000,
+            false_positive_rate: 0.001,
+            window_ms: 60 * 1000, // JSの CACHE_TTL と同じ1分
+        }
+    }
+}
+
+impl DedupConfig {
+    /// 設定値が有効な範囲にあることを確認
+    pub fn validate(&self) -> Result<()> {
+        let error = |message: &str| Err(TrendError::parse("重複除外設定", message));
+        if self.expected_views == 0 {
+            return error("expected_viewsには1以上を指定してください");
+        }
+        if !(self.false_positive_rate > 0.0 && self.false_positive_rate < 1.0) {
+            return error("false_positive_rateには0より大きく1未満の値を指定してください");
+        }
+        if self.window_ms == 0 {
+            return error("window_msには1以上を指定してください");
+        }
+        self.dimensions().map(|_| ())
+    }
+
+    /// 偽陽性率を満たすビット数とハッシュ関数の数
+    ///
+    /// ビット数が`MAX_FILTER_BITS`を超える設定はエラーとする。
+    fn dimensions(&self) -> Result<(u64, u32)> {
+        let too_large = || {
+            TrendError::parse(
+                "重複除外設定",
+                format!("expected_viewsとfalse_positive_rateに対してフィルターが大きすぎます（最大{}ビット）", MAX_FILTER_BITS),
+            )
+        };
+        let n = self.expected_views as f64;
+        let ln2 = std::f64::consts::LN_2;
+        let bits = (-n * self.false_positive_rate.ln() / (ln2 * ln2)).ceil().max(64.0);
+        if bits > MAX_FILTER_BITS as f64 {
+            return Err(too_large());
+        }
+        let bits = (bits as u64)
+            .div_ceil(64)
+            .checked_mul(64)
+            .filter(|bits| *bits <= MAX_FILTER_BITS)
+            .ok_or_else(too_large)?;
+        let hashes = ((bits as f64 / n) * ln2).round().clamp(1.0, 32.0) as u32;
+        Ok((bits, hashes))
+    }
+}
+
+/// 重複除外の統計（JSの`stats`と同じ項目）
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct DedupStats {
+    pub total_views: u64,
+    pub unique_views: u64,
+    pub duplicate_views: u64,
+}
+
+/// 2世代のBloomフィルターで重複閲覧を除外する
+pub struct ViewDeduplicator {
+    config: DedupConfig,
+    num_bits: u64,
+    num_hashes: u32,
+    current: Vec<u64>,
+    previous: Vec<u64>,
+    current_start: Option<u64>,
+    stats: DedupStats,
+    clock: Box<dyn Clock>,
+}
+
+impl ViewDeduplicator {
+    /// 設定から重複除外器を作成
+    pub fn new(config: DedupConfig) -> Result<Self> {
+        config.validate()?;
+        let (num_bits, num_hashes) = config.dimensions()?;
+        let words = (num_bits / 64) as usize;
+        Ok(ViewDeduplicator {
+            config,
+            num_bits,
+            num_hashes,
+            current: vec![0; words],
+            previous: vec![0; words],
+            current_start: None,
+            stats: DedupStats::default(),
+            clock: Box::new(SystemClock),
+        })
+    }
+
+    /// 現在時刻の取得元を差し替える
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    /// 設定を取得
+    pub fn config(&self) -> &DedupConfig {
+        &self.config
+    }
+
+    /// 統計を取得
+    pub fn stats(&self) -> &DedupStats {
+        &self.stats
+    }
+
+    /// 閲覧を記録し、新規閲覧ならtrueを返す
+    pub fn record(&mut self, post_id: u32, user_key: &str) -> bool {
+        let now = self.clock.now_ms();
+        self.record_at(post_id, user_key, now)
+    }
+
+    /// 指定時刻（UNIXミリ秒）の閲覧として記録し、新規閲覧ならtrueを返す
+    pub fn record_at(&mut self, post_id: u32, user_key: &str, now: u64) -> bool {
+        self.rotate(now);
+        let positions = self.positions(post_id, user_key);
+        self.stats.total_views += 1;
+
+        if contains(&self.current, &positions) || contains(&self.previous, &positions) {
+            self.stats.duplicate_views += 1;
+            return false;
+        }
+
+        for position in positions {
+            self.current[(position / 64) as usize] |= 1 << (position % 64);
+        }
+        self.stats.unique_views += 1;
+        true
+    }
+
+    /// 記録せずに、指定時刻の時点で重複とみなされるかを確認
+    pub fn is_duplicate_at(&self, post_id: u32, user_key: &str, now: u64) -> bool {
+        let positions = self.positions(post_id, user_key);
+        match self.current_start {
+            Some(start) if now >= start.saturating_add(self.config.window_ms.saturating_mul(2)) => false,
+            Some(start) if now >= start.saturating_add(self.config.window_ms) => contains(&self.current, &positions),
+            Some(_) => contains(&self.current, &positions) || contains(&self.previous, &positions),
+            None => false,
+        }
+    }
+
+    /// 状態をバイト列として保存
+    pub fn snapshot(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(SNAPSHOT_HEADER_SIZE + self.current.len() * 16);
+        bytes.extend_from_slice(SNAPSHOT_MAGIC);
+        bytes.extend_from_slice(&[SNAPSHOT_VERSION, 0, 0, 0]);
+        bytes.extend_from_slice(&self.config.expected_views.to_le_bytes());
+        bytes.extend_from_slice(&self.config.false_positive_rate.to_le_bytes());
+        bytes.extend_from_slice(&self.config.window_ms.to_le_bytes());
+        bytes.extend_from_slice(&self.num_bits.to_le_bytes());
+        bytes.extend_from_slice(&self.current_start.unwrap_or(NOT_STARTED).to_le_bytes());
+        bytes.extend_from_slice(&self.stats.total_views.to_le_bytes());
+        bytes.extend_from_slice(&self.stats.unique_views.to_le_bytes());
+        bytes.extend_from_slice(&self.stats.duplicate_views.to_le_bytes());
+        for word in self.current.iter().chain(&self.previous) {
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// スナップショットから状態を復元
+    ///
+    /// フィルター部分のサイズをヘッダーのビット数と照合してから確保する。
+    pub fn restore(bytes: &[u8]) -> Result<Self> {
+        let invalid = |message: &str| TrendError::InvalidSnapshot {
+            message: message.to_string(),
+        };
+        if bytes.len() < SNAPSHOT_HEADER_SIZE || &bytes[..4] != SNAPSHOT_MAGIC {
+            return Err(invalid("VDDPヘッダーがありません"));
+        }
+        if bytes[4] != SNAPSHOT_VERSION {
+            return Err(invalid(&format!("未対応のバージョンです: {}", bytes[4])));
+        }
+
+        let field = |index: usize| {
+            let offset = 8 + index * 8;
+            u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap_or_default())
+        };
+        let config = DedupConfig {
+            expected_views: field(0),
+            false_positive_rate: f64::from_bits(field(1)),
+            window_ms: field(2),
+        };
+        config.validate().map_err(|e| invalid(&e.to_string()))?;
+        let (num_bits, num_hashes) = config.dimensions().map_err(|e| invalid(&e.to_string()))?;
+        if field(3) != num_bits {
+            return Err(invalid("ビット数が設定と一致しません"));
+        }
+
+        // 2世代分のフィルター（ビット数はMAX_FILTER_BITS以下のため桁あふれしない）
+        let words = (num_bits / 64) as usize;
+        let body = &bytes[SNAPSHOT_HEADER_SIZE..];
+        if body.len() as u64 != num_bits / 64 * 16 {
+            return Err(invalid(&format!("フィルター部分のサイズが不正です: {}バイト", body.len())));
+        }
+        let mut chunks = body
+            .chunks_exact(8)
+            .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap_or_default()));
+
+        Ok(ViewDeduplicator {
+            config,
+            num_bits,
+            num_hashes,
+            current: chunks.by_ref().take(words).collect(),
+            previous: chunks.collect(),
+            current_start: Some(field(4)).filter(|start| *start != NOT_STARTED),
+            stats: DedupStats {
+                total_views: field(5),
+                unique_views: field(6),
+                duplicate_views: field(7),
+            },
+            clock: Box::new(SystemClock),
+        })
+    }
+
+    /// 期間が過ぎた世代を入れ替える
+    fn rotate(&mut self, now: u64) {
+        let window = self.config.window_ms;
+        let start = *self.current_start.get_or_insert(now);
+        if now < start.saturating_add(window) {
+            return;
+        }
+
+        let elapsed = (now - start) / window;
+        if elapsed >= 2 {
+            self.previous.fill(0);
+        } else {
+            std::mem::swap(&mut self.current, &mut self.previous);
+        }
+        self.current.fill(0);
+        self.current_start = Some(start + elapsed * window);
+    }
+
+    /// (投稿, ユーザー)に対応するビット位置（ダブルハッシュ法）
+    fn positions(&self, post_id: u32, user_key: &str) -> Vec<u64> {
+        let mut key = Vec::with_capacity(4 + user_key.len());
+        key.extend_from_slice(&post_id.to_le_bytes());
+        key.extend_from_slice(user_key.as_bytes());
+
+        let h1 = murmur_hash64a(&key, HASH_SEED_1);
+        let h2 = murmur_hash64a(&key, HASH_SEED_2) | 1;
+        (0..self.num_hashes as u64)
+            .map(|i| h1.wrapping_add(i.wrapping_mul(h2)) % self.num_bits)
+            .collect()
+    }
+}
+
+fn contains(bits: &[u64], positions: &[u64]) -> bool {
+    positions
+        .iter()
+        .all(|position| bits[(position / 64) as usize] & (1 << (position % 64)) != 0)
+}
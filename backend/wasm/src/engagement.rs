@@ -0,0 +1,121 @@
+//! 閲覧数の少ない投稿のエンゲージメント率の平滑化
+//!
+//! 閲覧2回・本棚追加1回の投稿は本棚追加率50%になり、エンゲージメントだけで上位に入ってしまう。
+//! サイト全体の率（事前分布）を`prior_views`回分の閲覧として加え、証拠が少ない投稿の率を
+//! サイト全体の率へ引き寄せる。
+//!
+//! - `BayesianAverage`: (件数 + 全体の率 × prior_views) / (閲覧数 + prior_views)
+//! - `WilsonLowerBound`: 上と同じ率のWilsonスコア信頼区間の下限（閲覧数が少ないほど低くなる）
+//!
+//! サイト全体の率は`EngagementRates::from_posts`で投稿の一覧から求め、
+//! `EngagementConfig::prior`に設定する。
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{EngagementConfig, EngagementModel, EngagementWeights};
+use crate::trend_calculator::RedisHllData;
+
+/// 1投稿分の閲覧数と行動の件数
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct EngagementCounts {
+    pub views: u32,
+    pub likes: u32,
+    pub comments: u32,
+    pub bookmarks: u32,
+}
+
+impl From<&RedisHllData> for EngagementCounts {
+    fn from(data: &RedisHllData) -> Self {
+        EngagementCounts {
+            views: data.view_count,
+            likes: data.like_count,
+            comments: data.comment_count,
+            bookmarks: data.bookmark_count,
+        }
+    }
+}
+
+/// 閲覧1回あたりの行動の率
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct EngagementRates {
+    pub like: f64,
+    pub comment: f64,
+    pub bookmark: f64,
+}
+
+impl Default for EngagementRates {
+    fn default() -> Self {
+        EngagementRates {
+            like: 0.05,
+            comment: 0.01,
+            bookmark: 0.02,
+        }
+    }
+}
+
+impl EngagementRates {
+    /// 投稿の一覧からサイト全体の率（全投稿の件数の合計 / 閲覧数の合計）を求める
+    ///
+    /// 閲覧数の合計が0の場合は`None`。
+    pub fn from_posts(posts: &[EngagementCounts]) -> Option<Self> {
+        let sum = |count: fn(&EngagementCounts) -> u32| posts.iter().map(|p| count(p) as f64).sum::<f64>();
+        let views = sum(|p| p.views);
+        if views == 0.0 {
+            return None;
+        }
+        Some(EngagementRates {
+            like: sum(|p| p.likes) / views,
+            comment: sum(|p| p.comments) / views,
+            bookmark: sum(|p| p.bookmarks) / views,
+        })
+    }
+
+    /// 率の重み付き和
+    pub fn weighted(&self, weights: &EngagementWeights) -> f64 {
+        self.like * weights.like + self.comment * weights.comment + self.bookmark * weights.bookmark
+    }
+}
+
+/// 設定のモデルで閲覧1回あたりの行動の率を求める
+///
+/// `Raw`では件数 / 閲覧数（閲覧数0なら0）をそのまま返す。
+pub fn smooth_rates(counts: &EngagementCounts, config: &EngagementConfig) -> EngagementRates {
+    let rate = |count: u32, prior: f64| {
+        let views = counts.views as f64;
+        match config.model {
+            EngagementModel::Raw if counts.views == 0 => 0.0,
+            EngagementModel::Raw => count as f64 / views,
+            EngagementModel::BayesianAverage => bayesian_average(count as f64, views, prior, config.prior_views),
+            EngagementModel::WilsonLowerBound => {
+                wilson_lower_bound(count as f64 + prior * config.prior_views, views + config.prior_views, config.wilson_z)
+            }
+        }
+    };
+    EngagementRates {
+        like: rate(counts.likes, config.prior.like),
+        comment: rate(counts.comments, config.prior.comment),
+        bookmark: rate(counts.bookmarks, config.prior.bookmark),
+    }
+}
+
+/// 事前分布の率を`prior_views`回分の閲覧として加えた率
+fn bayesian_average(count: f64, views: f64, prior: f64, prior_views: f64) -> f64 {
+    if views + prior_views == 0.0 {
+        return prior;
+    }
+    (count + prior * prior_views) / (views + prior_views)
+}
+
+/// Wilsonスコア信頼区間の下限（率は0〜1に丸める）
+fn wilson_lower_bound(successes: f64, trials: f64, z: f64) -> f64 {
+    if trials <= 0.0 {
+        return 0.0;
+    }
+    let p = (successes / trials).clamp(0.0, 1.0);
+    let z2 = z * z;
+    let center = p + z2 / (2.0 * trials);
+    let margin = z * (p * (1.0 - p) / trials + z2 / (4.0 * trials * trials)).sqrt();
+    ((center - margin) / (1.0 + z2 / trials)).max(0.0)
+}
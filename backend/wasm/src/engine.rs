@@ -0,0 +1,223 @@
+//! 複数投稿・複数期間のトレンド計算をまとめるレジストリ
+//!
+//! `TrendCalculator`は1つの投稿・1つの期間に固定されるため、4期間 × 投稿数の
+//! 計算機とイベントのコピーが必要になる。`TrendEngine`は投稿ごとのイベントの集計を
+//! 1つだけ持ち、同じデータから全期間のスコアとランキングを1回の走査で作る。
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::batch::BatchError;
+use crate::clock::{Clock, SystemClock, TimeSkewPolicy};
+use crate::config::ScoringConfig;
+use crate::error::{Result, TrendError};
+use crate::period::Period;
+use crate::ranking::{rank_top_n, RankedPost, RankingKey};
+use crate::streaming::PostActivity;
+use crate::trend_calculator::{TrendCalculator, TrendStats, ViewEvent, WindowMetrics};
+
+/// 1期間分のランキング
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct PeriodRanking {
+    pub period: Period,
+    pub ranking: Vec<RankedPost>, // スコア降順の上位N件
+    pub errors: Vec<BatchError>,  // 計算に失敗した投稿
+}
+
+/// 登録済みの投稿
+#[derive(Default)]
+struct EnginePost {
+    activity: PostActivity,
+    suspicion: f64,
+}
+
+/// 複数投稿・複数期間のトレンド計算機
+pub struct TrendEngine {
+    posts: BTreeMap<u32, EnginePost>,
+    periods: Vec<Period>,
+    clock: Box<dyn Clock>,
+    skew_policy: TimeSkewPolicy,
+    config: Arc<ScoringConfig>,
+}
+
+impl Default for TrendEngine {
+    fn default() -> Self {
+        TrendEngine {
+            posts: BTreeMap::new(),
+            periods: Period::STANDARD.to_vec(),
+            clock: Box::new(SystemClock),
+            skew_policy: TimeSkewPolicy::default(),
+            config: Arc::new(ScoringConfig::default()),
+        }
+    }
+}
+
+impl TrendEngine {
+    /// 標準の4期間（日次・週次・月次・年次）を計算するレジストリを作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 計算する期間を差し替える（重複は除く）
+    pub fn with_periods(mut self, periods: &[Period]) -> Self {
+        self.periods.clear();
+        for period in periods {
+            if !self.periods.contains(period) {
+                self.periods.push(*period);
+            }
+        }
+        self
+    }
+
+    /// 現在時刻の取得元を差し替える
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    /// 現在時刻の取得元を設定
+    pub fn set_clock(&mut self, clock: impl Clock + 'static) {
+        self.clock = Box::new(clock);
+    }
+
+    /// 未来のタイムスタンプの扱いを差し替える
+    pub fn with_skew_policy(mut self, policy: TimeSkewPolicy) -> Self {
+        self.skew_policy = policy;
+        self
+    }
+
+    /// スコア計算の設定を差し替える
+    pub fn with_config(mut self, config: Arc<ScoringConfig>) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// 計算する期間
+    pub fn periods(&self) -> &[Period] {
+        &self.periods
+    }
+
+    /// 投稿を登録する（登録済みならfalse）
+    pub fn add_post(&mut self, post_id: u32) -> bool {
+        if self.posts.contains_key(&post_id) {
+            return false;
+        }
+        self.posts.insert(post_id, EnginePost::default());
+        true
+    }
+
+    /// 投稿とそのデータを削除する（未登録ならfalse）
+    pub fn remove_post(&mut self, post_id: u32) -> bool {
+        self.posts.remove(&post_id).is_some()
+    }
+
+    /// 投稿が登録済みか
+    pub fn contains_post(&self, post_id: u32) -> bool {
+        self.posts.contains_key(&post_id)
+    }
+
+    /// 登録済みの投稿数
+    pub fn post_count(&self) -> usize {
+        self.posts.len()
+    }
+
+    /// 登録済みの投稿ID（昇順）
+    pub fn post_ids(&self) -> Vec<u32> {
+        self.posts.keys().copied().collect()
+    }
+
+    /// 投稿のイベントを取り込む（未登録の投稿・破棄済みの時刻より前のイベントはfalse）
+    pub fn push_event(&mut self, post_id: u32, event: &ViewEvent) -> bool {
+        self.posts
+            .get_mut(&post_id)
+            .is_some_and(|post| post.activity.push_event(event))
+    }
+
+    /// 投稿の集約済み時間窓を取り込む（未登録の投稿・破棄済みの窓はfalse）
+    pub fn push_window(&mut self, post_id: u32, window: WindowMetrics) -> bool {
+        self.posts
+            .get_mut(&post_id)
+            .is_some_and(|post| post.activity.push_window(window))
+    }
+
+    /// 投稿の疑わしさ（0〜1）を設定する（未登録ならfalse）
+    pub fn set_suspicion(&mut self, post_id: u32, suspicion: f64) -> bool {
+        match self.posts.get_mut(&post_id) {
+            Some(post) => {
+                post.suspicion = if suspicion.is_finite() { suspicion.clamp(0.0, 1.0) } else { 0.0 };
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 全投稿の指定時刻より前のデータを破棄する
+    ///
+    /// 最も長い期間に必要なデータを残すよう、呼び出し側で時刻を決める。
+    pub fn evict_before(&mut self, timestamp: u64) {
+        self.posts.values_mut().for_each(|post| post.activity.evict_before(timestamp));
+    }
+
+    /// 現在時刻で1投稿・1期間のスコアを計算
+    pub fn score_post(&self, post_id: u32, period: Period) -> Result<TrendStats> {
+        self.score_post_at(post_id, period, self.clock.now_ms())
+    }
+
+    /// 指定時刻（UNIXミリ秒）を現在時刻として1投稿・1期間のスコアを計算
+    pub fn score_post_at(&self, post_id: u32, period: Period, now: u64) -> Result<TrendStats> {
+        let post = self.posts.get(&post_id).ok_or(TrendError::UnknownPost { post_id })?;
+        post.activity.score(&self.calculator(post_id, period), post.suspicion, now)
+    }
+
+    /// 現在時刻で全期間のランキングを作成
+    pub fn rank(&self, top_n: usize) -> Vec<PeriodRanking> {
+        self.rank_at(top_n, self.clock.now_ms())
+    }
+
+    /// 指定時刻（UNIXミリ秒）を現在時刻として全期間のランキングを作成
+    ///
+    /// 投稿ごとに全期間のスコアをまとめて計算する。結果は`periods()`の順。
+    pub fn rank_at(&self, top_n: usize, now: u64) -> Vec<PeriodRanking> {
+        let mut calculators: Vec<TrendCalculator> =
+            self.periods.iter().map(|period| self.calculator(0, *period)).collect();
+        let mut keys: Vec<Vec<RankingKey>> = vec![Vec::with_capacity(self.posts.len()); self.periods.len()];
+        let mut errors: Vec<Vec<BatchError>> = vec![Vec::new(); self.periods.len()];
+
+        for (post_id, post) in &self.posts {
+            let last_activity = post.activity.last_activity().unwrap_or(0);
+            for (index, calculator) in calculators.iter_mut().enumerate() {
+                calculator.set_post_id(*post_id);
+                match post.activity.score(calculator, post.suspicion, now) {
+                    Ok(stats) => keys[index].push(RankingKey {
+                        post_id: *post_id,
+                        score: stats.score,
+                        unique_users: stats.unique_users,
+                        last_activity,
+                    }),
+                    Err(error) => errors[index].push(BatchError {
+                        post_id: *post_id,
+                        error,
+                    }),
+                }
+            }
+        }
+
+        self.periods
+            .iter()
+            .zip(keys.into_iter().zip(errors))
+            .map(|(period, (keys, errors))| PeriodRanking {
+                period: *period,
+                ranking: rank_top_n(keys, top_n),
+                errors,
+            })
+            .collect()
+    }
+
+    /// 期間ごとの計算機（時計は`rank_at`・`score_post_at`の時刻を使うため不要）
+    fn calculator(&self, post_id: u32, period: Period) -> TrendCalculator {
+        TrendCalculator::new(post_id, period)
+            .with_skew_policy(self.skew_policy)
+            .with_config(Arc::clone(&self.config))
+    }
+}
@@ -0,0 +1,104 @@
+//! 計算エンジンのエラー型
+
+use serde::Serialize;
+use std::fmt;
+
+/// 急上昇スコア計算で発生するエラー
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TrendError {
+    /// 入力JSONの解析に失敗した
+    Parse { target: String, message: String },
+    /// 未知の期間タイプが指定された
+    InvalidPeriod { value: String },
+    /// 現在時刻より未来のタイムスタンプが含まれている
+    FutureTimestamp { timestamp: u64, now: u64 },
+    /// 数値計算がオーバーフローした
+    Overflow { context: String },
+    /// 閲覧レコードの値がパック形式で表現できる範囲外
+    InvalidViewRecord { field: String, value: u64 },
+    /// バッファ長がレコード長と合わない
+    InvalidBufferLength { length: usize, record_len: usize },
+    /// 単一レコードの長さがどの形式のレコード長とも一致しない
+    UnknownRecordLength { length: usize, accepted: Vec<usize> },
+    /// HyperLogLogのバイト列を解釈できない
+    InvalidSketch { message: String },
+    /// 重複除外のスナップショットを復元できない
+    InvalidSnapshot { message: String },
+    /// TrendEngineに登録されていない投稿が指定された
+    UnknownPost { post_id: u32 },
+    /// 計算したスコアがNaNまたは無限大になった
+    NonFiniteScore { score: f64 },
+}
+
+impl TrendError {
+    /// JSON解析エラーを作成
+    pub fn parse(target: &str, err: impl fmt::Display) -> Self {
+        TrendError::Parse {
+            target: target.to_string(),
+            message: err.to_string(),
+        }
+    }
+
+    /// オーバーフローエラーを作成
+    pub fn overflow(context: &str) -> Self {
+        TrendError::Overflow {
+            context: context.to_string(),
+        }
+    }
+
+    /// JS側で判別に使うエラーコード
+    pub fn code(&self) -> &'static str {
+        match self {
+            TrendError::Parse { .. } => "PARSE_ERROR",
+            TrendError::InvalidPeriod { .. } => "INVALID_PERIOD",
+            TrendError::FutureTimestamp { .. } => "FUTURE_TIMESTAMP",
+            TrendError::Overflow { .. } => "OVERFLOW",
+            TrendError::InvalidViewRecord { .. } => "INVALID_VIEW_RECORD",
+            TrendError::InvalidBufferLength { .. } => "INVALID_BUFFER_LENGTH",
+            TrendError::UnknownRecordLength { .. } => "UNKNOWN_RECORD_LENGTH",
+            TrendError::InvalidSketch { .. } => "INVALID_SKETCH",
+            TrendError::InvalidSnapshot { .. } => "INVALID_SNAPSHOT",
+            TrendError::UnknownPost { .. } => "UNKNOWN_POST",
+            TrendError::NonFiniteScore { .. } => "NON_FINITE_SCORE",
+        }
+    }
+}
+
+impl fmt::Display for TrendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrendError::Parse { target, message } => {
+                write!(f, "{}のJSONを解析できませんでした: {}", target, message)
+            }
+            TrendError::InvalidPeriod { value } => write!(f, "不正な期間タイプです: {}", value),
+            TrendError::FutureTimestamp { timestamp, now } => write!(
+                f,
+                "タイムスタンプが現在時刻より未来です: timestamp={}, now={}",
+                timestamp, now
+            ),
+            TrendError::Overflow { context } => write!(f, "数値がオーバーフローしました: {}", context),
+            TrendError::InvalidViewRecord { field, value } => {
+                write!(f, "閲覧レコードの値が範囲外です: {}={}", field, value)
+            }
+            TrendError::InvalidBufferLength { length, record_len } => write!(
+                f,
+                "バッファ長が不正です: length={}（レコード長{}バイト）",
+                length, record_len
+            ),
+            TrendError::UnknownRecordLength { length, accepted } => {
+                let accepted: Vec<String> = accepted.iter().map(|len| len.to_string()).collect();
+                write!(f, "レコード長から形式を判別できません: length={}（{}バイトのいずれか）", length, accepted.join("・"))
+            }
+            TrendError::InvalidSketch { message } => write!(f, "HyperLogLogを読み込めませんでした: {}", message),
+            TrendError::InvalidSnapshot { message } => write!(f, "スナップショットを復元できませんでした: {}", message),
+            TrendError::UnknownPost { post_id } => write!(f, "登録されていない投稿です: {}", post_id),
+            TrendError::NonFiniteScore { score } => write!(f, "スコアが有限の値になりませんでした: {}", score),
+        }
+    }
+}
+
+impl std::error::Error for TrendError {}
+
+/// 計算エンジン共通のResult型
+pub type Result<T> = std::result::Result<T, TrendError>;
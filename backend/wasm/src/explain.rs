@@ -0,0 +1,114 @@
+//! スコアの内訳
+//!
+//! スコアは「項（値 × 重み）の和」に「係数（時間減衰など）の積」を掛けたものとして表す。
+//! 各項の寄与は係数を掛けた後の値なので、寄与の合計が丸める前のスコアになる。
+
+use serde::{Deserialize, Serialize};
+
+/// スコアに加算される項
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScoreTerm {
+    pub name: String,
+    pub value: f64,        // 正規化後の値
+    pub weight: f64,       // 重み（スケールと期間ごとの重みの積）
+    pub contribution: f64, // 係数を掛けた後のスコアへの寄与
+}
+
+/// スコアに掛けられる係数
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScoreFactor {
+    pub name: String,
+    pub value: f64,
+}
+
+/// 前回の内訳からの項ごとの変化
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScoreChange {
+    pub name: String,
+    pub previous: f64, // 前回の寄与（係数の場合は値）
+    pub current: f64,
+    pub change: f64,
+}
+
+/// スコアの内訳
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct ScoreExplanation {
+    pub terms: Vec<ScoreTerm>,
+    pub base_score: f64, // 項の和（係数を掛ける前）
+    pub factors: Vec<ScoreFactor>,
+    pub score: f64, // 丸める前のスコア
+}
+
+impl ScoreExplanation {
+    /// 項を追加
+    pub(crate) fn term(mut self, name: &str, value: f64, weight: f64) -> Self {
+        self.terms.push(ScoreTerm {
+            name: name.to_string(),
+            value,
+            weight,
+            contribution: 0.0,
+        });
+        self
+    }
+
+    /// 係数を追加
+    pub(crate) fn factor(mut self, name: &str, value: f64) -> Self {
+        self.factors.push(ScoreFactor {
+            name: name.to_string(),
+            value,
+        });
+        self
+    }
+
+    /// 項と係数からスコアと各項の寄与を求める
+    pub(crate) fn finish(mut self) -> Self {
+        let multiplier: f64 = self.factors.iter().map(|f| f.value).product();
+        self.base_score = self.terms.iter().map(|t| t.value * t.weight).sum();
+        for term in &mut self.terms {
+            term.contribution = term.value * term.weight * multiplier;
+        }
+        self.score = self.base_score * multiplier;
+        self
+    }
+
+    /// 名前で項を取得
+    pub fn term_named(&self, name: &str) -> Option<&ScoreTerm> {
+        self.terms.iter().find(|t| t.name == name)
+    }
+
+    /// 名前で係数を取得
+    pub fn factor_named(&self, name: &str) -> Option<f64> {
+        self.factors.iter().find(|f| f.name == name).map(|f| f.value)
+    }
+
+    /// 前回の内訳と比べ、変化の大きい順に項と係数の変化を返す
+    ///
+    /// 項は寄与、係数は値で比べる。片方にしかないものは0または1として扱う。
+    pub fn changes_since(&self, previous: &ScoreExplanation) -> Vec<ScoreChange> {
+        let mut changes: Vec<ScoreChange> = Vec::new();
+        let mut push = |name: &str, previous: f64, current: f64| {
+            changes.push(ScoreChange {
+                name: name.to_string(),
+                previous,
+                current,
+                change: current - previous,
+            })
+        };
+
+        for term in &self.terms {
+            push(&term.name, previous.term_named(&term.name).map_or(0.0, |t| t.contribution), term.contribution);
+        }
+        for term in previous.terms.iter().filter(|t| self.term_named(&t.name).is_none()) {
+            push(&term.name, term.contribution, 0.0);
+        }
+        for factor in &self.factors {
+            push(&factor.name, previous.factor_named(&factor.name).unwrap_or(1.0), factor.value);
+        }
+        for factor in previous.factors.iter().filter(|f| self.factor_named(&f.name).is_none()) {
+            push(&factor.name, factor.value, 1.0);
+        }
+
+        changes.sort_by(|a, b| b.change.abs().total_cmp(&a.change.abs()).then_with(|| a.name.cmp(&b.name)));
+        changes
+    }
+}
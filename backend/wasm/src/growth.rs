@@ -0,0 +1,266 @@
+//! 時間ごとの閲覧数からの成長率・モメンタムの推定
+//!
+//! 推定方法は`GrowthConfig::estimator`で選ぶ。
+//!
+//! - `BlockCompare`: 直近N時間とその前のN時間の合計を比べ、直近の時間どうしの増加率を平均する
+//!   （従来の方法）。閲覧のない時間は飛ばすため、1→5のような少数の変化で大きく振れる
+//! - `Ewma`: 0で埋めた時間ごとの系列の短期・長期の指数移動平均を比べる
+//! - `LogLinear`: log(閲覧数 + 事前カウント)に直線を当てはめ、傾きを信頼度で0に向けて縮める
+//!
+//! `Ewma`・`LogLinear`は各時間の閲覧数に事前カウントを加えるため、閲覧の少ない投稿でも
+//! 1時間ごとに値が跳ねにくい。
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::config::{GrowthConfig, GrowthEstimator};
+
+/// 成長率・モメンタムの推定値
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct GrowthEstimate {
+    pub growth_rate: f64, // 直近N時間のその前のN時間に対する増加率（-1.0以上）
+    pub momentum: f64,    // 直近の1時間あたりの増加率（-1.0〜2.0）
+    pub confidence: f64,  // 推定の信頼度（0.0〜1.0、`BlockCompare`では常に1.0）
+}
+
+/// 時間ごとの閲覧数を、現在の時間で終わる`hours`時間の系列（古い順）にする
+///
+/// 閲覧のない時間は0で埋める。現在より後の時間の閲覧数は現在の時間に含める。
+pub fn zero_filled_hours(hourly_counts: &HashMap<u64, u32>, current_hour: u64, hours: u64) -> Vec<u32> {
+    let hours = hours.max(1);
+    let first_hour = current_hour.saturating_sub(hours - 1);
+    let mut series = vec![0u32; (current_hour - first_hour + 1) as usize];
+    for (&hour, &count) in hourly_counts {
+        if hour >= first_hour {
+            let index = (hour.min(current_hour) - first_hour) as usize;
+            series[index] = series[index].saturating_add(count);
+        }
+    }
+    series
+}
+
+/// 時間ごとの閲覧数の系列（古い順、最後が現在の時間）から成長率・モメンタムを推定
+///
+/// `compare_hours`は比較する時間数（N）。系列は2N時間あればよい。
+pub fn estimate_growth(series: &[u32], compare_hours: u64, config: &GrowthConfig) -> GrowthEstimate {
+    let compare_hours = compare_hours.max(1);
+    match config.estimator {
+        GrowthEstimator::BlockCompare => {
+            let current_hour = series.len().saturating_sub(1) as u64;
+            let hourly_counts: HashMap<u64, u32> = series
+                .iter()
+                .enumerate()
+                .filter(|(_, count)| **count > 0)
+                .map(|(hour, count)| (hour as u64, *count))
+                .collect();
+            block_compare(&hourly_counts, current_hour, compare_hours)
+        }
+        GrowthEstimator::Ewma => ewma(series, compare_hours, config),
+        GrowthEstimator::LogLinear => log_linear(series, compare_hours, config),
+    }
+}
+
+/// 時間（UNIXミリ秒 / 1時間）ごとの閲覧数から成長率・モメンタムを推定
+pub(crate) fn estimate_from_counts(
+    hourly_counts: &HashMap<u64, u32>,
+    current_hour: u64,
+    compare_hours: u64,
+    config: &GrowthConfig,
+) -> GrowthEstimate {
+    let compare_hours = compare_hours.max(1);
+    match config.estimator {
+        // 現在より後の時間も別の時間として扱う従来の動作を保つため、系列にしない
+        GrowthEstimator::BlockCompare => block_compare(hourly_counts, current_hour, compare_hours),
+        _ => {
+            let series = zero_filled_hours(hourly_counts, current_hour, compare_hours.saturating_mul(2));
+            estimate_growth(&series, compare_hours, config)
+        }
+    }
+}
+
+/// 直近N時間とその前のN時間の比較（従来の方法）
+fn block_compare(hourly_counts: &HashMap<u64, u32>, current_hour: u64, hours_to_compare: u64) -> GrowthEstimate {
+    if hourly_counts.is_empty() {
+        return GrowthEstimate {
+            confidence: 1.0,
+            ..GrowthEstimate::default()
+        };
+    }
+
+    // 時間ごとのカウントを時系列順にソート
+    let mut sorted_hours: Vec<_> = hourly_counts.iter().collect();
+    sorted_hours.sort_by_key(|&(hour, _)| *hour);
+
+    // 最新期間と過去期間のカウント合計
+    let mut recent_count = 0;
+    let mut previous_count = 0;
+
+    for &(hour, count) in &sorted_hours {
+        let hours_ago = current_hour.saturating_sub(*hour); // オーバーフロー防止
+
+        if hours_ago < hours_to_compare {
+            // 最新期間
+            recent_count += count;
+        } else if hours_ago < hours_to_compare.saturating_mul(2) {
+            // 過去期間
+            previous_count += count;
+        }
+    }
+
+    // 成長率を計算
+    let growth_rate = if previous_count == 0 {
+        if recent_count > 0 {
+            2.0
+        } else {
+            0.0
+        } // 新規の場合は固定値
+    } else {
+        let raw_growth = (recent_count as f64 - previous_count as f64) / previous_count as f64;
+        raw_growth.max(-1.0) // 下限を-100%に制限
+    };
+
+    // 十分なデータがない場合の固定値
+    let fallback = if growth_rate > 0.0 { 0.5 } else { 0.0 };
+
+    // 最新のいくつかの時間の傾向を分析
+    let latest_hours: Vec<_> = sorted_hours
+        .iter()
+        .filter(|&(hour, _)| current_hour.saturating_sub(**hour) < hours_to_compare)
+        .collect();
+
+    let momentum = if latest_hours.len() < 3 {
+        fallback
+    } else {
+        // 最新時間の傾きを計算
+        let mut sum_slope = 0.0;
+        let mut count = 0;
+
+        for i in 1..latest_hours.len() {
+            let (_, current_count) = latest_hours[i];
+            let (_, prev_count) = latest_hours[i - 1];
+
+            if **prev_count > 0 {
+                let slope = (**current_count as f64 - **prev_count as f64) / **prev_count as f64;
+
+                sum_slope += slope;
+                count += 1;
+            }
+        }
+
+        // 平均傾きをモメンタムとして使用
+        if count > 0 {
+            (sum_slope / count as f64).clamp(-1.0, 2.0)
+        } else {
+            fallback
+        }
+    };
+
+    GrowthEstimate {
+        growth_rate,
+        momentum,
+        confidence: 1.0,
+    }
+}
+
+/// 半減期（時間）から指数移動平均の平滑化係数を求める
+fn smoothing_factor(half_life_hours: f64) -> f64 {
+    1.0 - 0.5f64.powf(1.0 / half_life_hours.max(1.0))
+}
+
+/// 短期・長期の指数移動平均の比較
+///
+/// 成長率は短期平均の長期平均に対する比、モメンタムは短期平均の1時間あたりの変化を
+/// さらに平滑化したもの。信頼度は閲覧数の合計が事前カウントの合計に占める割合。
+fn ewma(series: &[u32], compare_hours: u64, config: &GrowthConfig) -> GrowthEstimate {
+    if series.is_empty() {
+        return GrowthEstimate::default();
+    }
+    let prior = config.count_prior;
+    let fast_alpha = smoothing_factor(compare_hours as f64 * config.fast_half_life_ratio);
+    let slow_alpha = smoothing_factor(compare_hours as f64 * config.slow_half_life_ratio);
+
+    // 系列の平均から始めることで、先頭の時間に引きずられないようにする
+    let total: f64 = series.iter().map(|c| *c as f64).sum();
+    let mean = total / series.len() as f64;
+    let (mut fast, mut slow, mut trend) = (mean, mean, 0.0);
+    for count in series {
+        let previous = fast;
+        fast += fast_alpha * (*count as f64 - fast);
+        slow += slow_alpha * (*count as f64 - slow);
+        trend += fast_alpha * ((fast - previous) - trend);
+    }
+
+    GrowthEstimate {
+        growth_rate: (fast + prior) / (slow + prior) - 1.0,
+        momentum: (trend / (slow + prior)).clamp(-1.0, 2.0),
+        confidence: total / (total + prior * series.len() as f64),
+    }
+}
+
+/// log(閲覧数 + 事前カウント)の重み付き最小二乗直線の傾き（1時間あたり）と信頼度
+///
+/// 重みは直近の時間ほど大きく、`half_life`時間前の重みは直近の半分（`None`なら均等）。
+/// 信頼度は t² / (t² + 1)（tは傾きの標準誤差に対する比）で、傾きに掛けて0に向けて縮める。
+/// 3時間未満の系列では傾きを推定できないため信頼度0。
+fn log_slope(series: &[u32], prior: f64, half_life: Option<f64>) -> (f64, f64) {
+    let n = series.len();
+    if n < 3 {
+        return (0.0, 0.0);
+    }
+    let weights: Vec<f64> = (0..n)
+        .map(|i| half_life.map_or(1.0, |h| 0.5f64.powf((n - 1 - i) as f64 / h.max(1.0))))
+        .collect();
+    let ys: Vec<f64> = series.iter().map(|c| (*c as f64 + prior).ln()).collect();
+    let weight_sum: f64 = weights.iter().sum();
+    let t_mean = weights.iter().enumerate().map(|(i, w)| w * i as f64).sum::<f64>() / weight_sum;
+    let y_mean = weights.iter().zip(&ys).map(|(w, y)| w * y).sum::<f64>() / weight_sum;
+
+    let mut sxx = 0.0;
+    let mut sxy = 0.0;
+    for (i, (w, y)) in weights.iter().zip(&ys).enumerate() {
+        let dt = i as f64 - t_mean;
+        sxx += w * dt * dt;
+        sxy += w * dt * (y - y_mean);
+    }
+    let slope = sxy / sxx;
+    if slope == 0.0 {
+        return (0.0, 0.0);
+    }
+
+    // 残差の分散（重みの偏りを有効なデータ数で補正）から傾きの分散を求める
+    let sse: f64 = weights
+        .iter()
+        .zip(&ys)
+        .enumerate()
+        .map(|(i, (w, y))| {
+            let residual = y - y_mean - slope * (i as f64 - t_mean);
+            w * residual * residual
+        })
+        .sum();
+    let effective_n = weight_sum * weight_sum / weights.iter().map(|w| w * w).sum::<f64>();
+    let residual_variance = sse / weight_sum * effective_n / (effective_n - 2.0).max(1.0);
+    let leverage: f64 = weights
+        .iter()
+        .enumerate()
+        .map(|(i, w)| (w * (i as f64 - t_mean)).powi(2))
+        .sum();
+    let variance = residual_variance * leverage / (sxx * sxx);
+    let confidence = slope * slope / (slope * slope + variance);
+    (slope, confidence)
+}
+
+/// 対数線形回帰
+///
+/// 成長率は系列全体の傾きをN時間分に換算したもの、モメンタムは直近の時間ほど重く
+/// （半減期N時間）当てはめた傾きを1時間あたりの増加率にしたもの。
+fn log_linear(series: &[u32], compare_hours: u64, config: &GrowthConfig) -> GrowthEstimate {
+    let prior = config.count_prior;
+    let (slope, confidence) = log_slope(series, prior, None);
+    let (recent_slope, recent_confidence) = log_slope(series, prior, Some(compare_hours as f64));
+
+    GrowthEstimate {
+        growth_rate: (slope * confidence * compare_hours as f64).exp() - 1.0,
+        momentum: ((recent_slope * recent_confidence).exp() - 1.0).clamp(-1.0, 2.0),
+        confidence,
+    }
+}
@@ -0,0 +1,368 @@
+//! Redisと互換性のあるHyperLogLog
+//!
+//! Redisの`PFADD`/`PFCOUNT`と同じハッシュ（MurmurHash64A、シード0xadc83b19）、
+//! レジスタ数（2^14）、推定式（Ertlの改良推定）を使うため、同じ要素からは同じ件数が得られる。
+//! `to_redis_bytes`の出力はそのまま Redis に`SET`して`PFCOUNT`/`PFMERGE`でき、
+//! Redis から`GET`した値（密・疎どちらの表現でも）を`from_redis_bytes`で読み込める。
+//!
+//! JSONではRedisの表現をBase64にした文字列としてシリアライズする。
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::error::{Result, TrendError};
+
+/// レジスタ番号に使うハッシュのビット数
+const HLL_P: u32 = 14;
+/// 先頭ゼロの数え上げに使うビット数
+const HLL_Q: usize = 64 - HLL_P as usize;
+const HLL_REGISTERS: usize = 1 << HLL_P;
+const HLL_BITS: usize = 6;
+const HLL_REGISTER_MAX: u8 = (1 << HLL_BITS) - 1;
+/// 密表現のレジスタ部分のバイト数
+const HLL_DENSE_SIZE: usize = (HLL_REGISTERS * HLL_BITS).div_ceil(8);
+const HLL_HEADER_SIZE: usize = 16;
+const HLL_MAGIC: &[u8; 4] = b"HYLL";
+const HLL_DENSE: u8 = 0;
+const HLL_SPARSE: u8 = 1;
+/// 疎表現を使う上限サイズ（Redisの`hll-sparse-max-bytes`の既定値）
+const HLL_SPARSE_MAX_BYTES: usize = 3000;
+const HLL_SPARSE_VAL_MAX: u8 = 32;
+const HLL_ALPHA_INF: f64 = 0.721_347_520_444_481_7;
+const HASH_SEED: u64 = 0xadc8_3b19;
+
+/// 期間のユニークユーザー数を推定するスケッチ
+#[derive(Clone, PartialEq, Eq)]
+pub struct HyperLogLog {
+    registers: Vec<u8>, // 1レジスタ1バイトで保持
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for HyperLogLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HyperLogLog").field("count", &self.count()).finish()
+    }
+}
+
+impl HyperLogLog {
+    /// 空のスケッチを作成
+    pub fn new() -> Self {
+        HyperLogLog {
+            registers: vec![0; HLL_REGISTERS],
+        }
+    }
+
+    /// 要素を追加する（レジスタが更新された場合はtrue）
+    pub fn add(&mut self, element: &[u8]) -> bool {
+        let hash = murmur_hash64a(element, HASH_SEED);
+        let index = (hash & (HLL_REGISTERS as u64 - 1)) as usize;
+        // 上位ビットに番兵を立て、下位から最初の1までの長さを数える
+        let rest = (hash >> HLL_P) | (1 << HLL_Q);
+        let count = rest.trailing_zeros() as u8 + 1;
+
+        if count > self.registers[index] {
+            self.registers[index] = count;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// ユーザーIDを追加する（`PFADD key 123`と同じく10進文字列として扱う）
+    pub fn add_user(&mut self, user_id: u32) -> bool {
+        self.add(user_id.to_string().as_bytes())
+    }
+
+    /// 別のスケッチを和集合として取り込む（`PFMERGE`と同じ）
+    pub fn merge(&mut self, other: &HyperLogLog) {
+        for (register, value) in self.registers.iter_mut().zip(&other.registers) {
+            *register = (*register).max(*value);
+        }
+    }
+
+    /// 要素が1つも追加されていないか
+    pub fn is_empty(&self) -> bool {
+        self.registers.iter().all(|r| *r == 0)
+    }
+
+    /// 推定ユニーク件数（`PFCOUNT`と同じ値）
+    pub fn count(&self) -> u64 {
+        let mut histogram = [0u32; 64];
+        for register in &self.registers {
+            histogram[*register as usize] += 1;
+        }
+
+        let m = HLL_REGISTERS as f64;
+        let mut z = m * tau((m - histogram[HLL_Q + 1] as f64) / m);
+        for j in (1..=HLL_Q).rev() {
+            z += histogram[j] as f64;
+            z *= 0.5;
+        }
+        z += m * sigma(histogram[0] as f64 / m);
+
+        (HLL_ALPHA_INF * m * m / z).round() as u64
+    }
+
+    /// Redisの文字列値として保存できるバイト列に変換
+    ///
+    /// Redisと同じく、小さいスケッチは疎表現、それ以外は密表現にする。
+    /// キャッシュ済み件数は無効扱いにしておき、Redis側で再計算させる。
+    pub fn to_redis_bytes(&self) -> Vec<u8> {
+        match self.encode_sparse() {
+            Some(sparse) => with_header(HLL_SPARSE, &sparse),
+            None => self.to_redis_dense_bytes(),
+        }
+    }
+
+    /// Redisの密表現のバイト列に変換
+    pub fn to_redis_dense_bytes(&self) -> Vec<u8> {
+        let mut dense = vec![0u8; HLL_DENSE_SIZE];
+        for (index, value) in self.registers.iter().enumerate() {
+            let bit = index * HLL_BITS;
+            let (byte, shift) = (bit / 8, bit % 8);
+            let value = *value as u16;
+            dense[byte] |= (value << shift) as u8;
+            if shift > 8 - HLL_BITS {
+                dense[byte + 1] |= (value >> (8 - shift)) as u8;
+            }
+        }
+        with_header(HLL_DENSE, &dense)
+    }
+
+    /// Redisの文字列値（密・疎表現）からスケッチを復元
+    pub fn from_redis_bytes(bytes: &[u8]) -> Result<HyperLogLog> {
+        if bytes.len() < HLL_HEADER_SIZE || &bytes[..4] != HLL_MAGIC {
+            return Err(invalid("HYLLヘッダーがありません"));
+        }
+
+        let body = &bytes[HLL_HEADER_SIZE..];
+        match bytes[4] {
+            HLL_DENSE => Self::decode_dense(body),
+            HLL_SPARSE => Self::decode_sparse(body),
+            encoding => Err(invalid(&format!("未知のエンコーディングです: {}", encoding))),
+        }
+    }
+
+    fn decode_dense(body: &[u8]) -> Result<HyperLogLog> {
+        if body.len() != HLL_DENSE_SIZE {
+            return Err(invalid(&format!("密表現のサイズが不正です: {}バイト", body.len())));
+        }
+
+        let registers = (0..HLL_REGISTERS)
+            .map(|index| {
+                let bit = index * HLL_BITS;
+                let (byte, shift) = (bit / 8, bit % 8);
+                let low = (body[byte] >> shift) as u16;
+                let high = body.get(byte + 1).map_or(0, |b| (*b as u16) << (8 - shift));
+                ((low | high) as u8) & HLL_REGISTER_MAX
+            })
+            .collect();
+        Ok(HyperLogLog { registers })
+    }
+
+    fn decode_sparse(body: &[u8]) -> Result<HyperLogLog> {
+        let mut registers = Vec::with_capacity(HLL_REGISTERS);
+        let mut i = 0;
+        while i < body.len() {
+            let op = body[i];
+            if op & 0x80 != 0 {
+                // VAL: 1vvvvvxx
+                let value = ((op >> 2) & 0x1f) + 1;
+                let run = (op & 0x3) as usize + 1;
+                registers.extend(std::iter::repeat_n(value, run));
+                i += 1;
+            } else if op & 0x40 != 0 {
+                // XZERO: 01xxxxxx yyyyyyyy
+                let next = *body.get(i + 1).ok_or_else(|| invalid("疎表現のXZEROが途中で切れています"))?;
+                let run = (((op & 0x3f) as usize) << 8 | next as usize) + 1;
+                registers.extend(std::iter::repeat_n(0, run));
+                i += 2;
+            } else {
+                // ZERO: 00xxxxxx
+                let run = (op & 0x3f) as usize + 1;
+                registers.extend(std::iter::repeat_n(0, run));
+                i += 1;
+            }
+            if registers.len() > HLL_REGISTERS {
+                break;
+            }
+        }
+
+        if registers.len() != HLL_REGISTERS {
+            return Err(invalid(&format!("疎表現のレジスタ数が不正です: {}", registers.len())));
+        }
+        Ok(HyperLogLog { registers })
+    }
+
+    /// 疎表現に変換（表現できない値がある場合や大きすぎる場合はNone）
+    fn encode_sparse(&self) -> Option<Vec<u8>> {
+        let mut sparse = Vec::new();
+        let mut i = 0;
+        while i < HLL_REGISTERS {
+            let value = self.registers[i];
+            let run = self.registers[i..].iter().take_while(|r| **r == value).count();
+
+            if value == 0 && run > 64 {
+                sparse.push(0x40 | ((run - 1) >> 8) as u8);
+                sparse.push(((run - 1) & 0xff) as u8);
+            } else if value == 0 {
+                sparse.push((run - 1) as u8);
+            } else if value > HLL_SPARSE_VAL_MAX {
+                return None;
+            } else {
+                let mut remaining = run;
+                while remaining > 0 {
+                    let len = remaining.min(4);
+                    sparse.push(0x80 | (value - 1) << 2 | (len - 1) as u8);
+                    remaining -= len;
+                }
+            }
+
+            if sparse.len() > HLL_SPARSE_MAX_BYTES {
+                return None;
+            }
+            i += run;
+        }
+        Some(sparse)
+    }
+}
+
+impl Serialize for HyperLogLog {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64_encode(&self.to_redis_bytes()))
+    }
+}
+
+impl<'de> Deserialize<'de> for HyperLogLog {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        let bytes = base64_decode(&encoded).map_err(serde::de::Error::custom)?;
+        HyperLogLog::from_redis_bytes(&bytes).map_err(serde::de::Error::custom)
+    }
+}
+
+fn invalid(message: &str) -> TrendError {
+    TrendError::InvalidSketch {
+        message: message.to_string(),
+    }
+}
+
+/// HYLLヘッダーを付ける（キャッシュ済み件数は無効フラグを立てる）
+fn with_header(encoding: u8, body: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HLL_HEADER_SIZE + body.len());
+    bytes.extend_from_slice(HLL_MAGIC);
+    bytes.extend_from_slice(&[encoding, 0, 0, 0]);
+    bytes.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 0x80]);
+    bytes.extend_from_slice(body);
+    bytes
+}
+
+/// Ertlの推定式のτ関数（Redisの`hllTau`）
+fn tau(mut x: f64) -> f64 {
+    if x == 0.0 || x == 1.0 {
+        return 0.0;
+    }
+    let mut y = 1.0;
+    let mut z = 1.0 - x;
+    loop {
+        x = x.sqrt();
+        let z_prime = z;
+        y *= 0.5;
+        z -= (1.0 - x).powi(2) * y;
+        if z_prime == z {
+            return z / 3.0;
+        }
+    }
+}
+
+/// Ertlの推定式のσ関数（Redisの`hllSigma`）
+fn sigma(mut x: f64) -> f64 {
+    if x == 1.0 {
+        return f64::INFINITY;
+    }
+    let mut y = 1.0;
+    let mut z = x;
+    loop {
+        x *= x;
+        let z_prime = z;
+        z += x * y;
+        y += y;
+        if z_prime == z {
+            return z;
+        }
+    }
+}
+
+/// Redisと同じMurmurHash64A（リトルエンディアンで読み込む）
+pub fn murmur_hash64a(data: &[u8], seed: u64) -> u64 {
+    const M: u64 = 0xc6a4_a793_5bd1_e995;
+    const R: u32 = 47;
+
+    let mut h = seed ^ (data.len() as u64).wrapping_mul(M);
+
+    let mut chunks = data.chunks_exact(8);
+    for chunk in &mut chunks {
+        let mut k = u64::from_le_bytes(chunk.try_into().unwrap_or_default());
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+        h ^= k;
+        h = h.wrapping_mul(M);
+    }
+
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        for (i, byte) in tail.iter().enumerate() {
+            h ^= (*byte as u64) << (8 * i);
+        }
+        h = h.wrapping_mul(M);
+    }
+
+    h ^= h >> R;
+    h = h.wrapping_mul(M);
+    h ^= h >> R;
+    h
+}
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64_ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+fn base64_decode(encoded: &str) -> std::result::Result<Vec<u8>, String> {
+    let trimmed = encoded.trim_end_matches('=');
+    let mut bytes = Vec::with_capacity(trimmed.len() * 3 / 4);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in trimmed.bytes() {
+        let value = BASE64_ALPHABET
+            .iter()
+            .position(|a| *a == c)
+            .ok_or_else(|| format!("Base64として不正な文字です: {}", c as char))?;
+        buffer = buffer << 6 | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Ok(bytes)
+}
@@ -1,5 +1,49 @@
-
+mod abuse;
+mod batch;
+mod clock;
+mod config;
+mod dedup;
+mod engagement;
+mod engine;
+mod error;
+mod explain;
+mod growth;
+mod hyperloglog;
+mod logging;
+mod packing;
+mod period;
+mod ranking;
+mod replay;
+mod rollup;
+mod scorer;
+mod series;
+mod streaming;
+mod total;
 mod trend_calculator;
 
+#[cfg(feature = "wasm")]
+mod wasm;
+
 // Re-export
-pub use trend_calculator::*;
\ No newline at end of file
+pub use abuse::*;
+pub use batch::*;
+pub use clock::*;
+pub use config::*;
+pub use dedup::*;
+pub use engagement::*;
+pub use engine::*;
+pub use error::*;
+pub use explain::*;
+pub use growth::*;
+pub use hyperloglog::*;
+pub use logging::*;
+pub use packing::*;
+pub use period::*;
+pub use ranking::*;
+pub use replay::*;
+pub use rollup::*;
+pub use scorer::*;
+pub use series::*;
+pub use streaming::*;
+pub use total::*;
+pub use trend_calculator::*;
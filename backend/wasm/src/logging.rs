@@ -0,0 +1,239 @@
+//! 計算ログの出力
+//!
+//! ログは`LogSink`（出力先）へ`LogRecord`として渡す。出力先と最低レベルは実行時に変更できる。
+//! 既定の出力先は何もしない`NoopSink`で、JS側に関数を用意しなくても動作する。
+//!
+//! 投稿ごとの計算過程（`log_calculation`）は`Trace`レベルで、`trace_post`で指定した
+//! 投稿のみ出力する。出力しない場合はデータの組み立て・シリアライズも行わない。
+
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use crate::error::{Result, TrendError};
+
+/// ログのレベル
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    #[default]
+    Warn,
+    Error,
+    Off, // 最低レベルとして指定した場合は何も出力しない（追跡中の投稿を除く）
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogLevel::Trace => write!(f, "trace"),
+            LogLevel::Debug => write!(f, "debug"),
+            LogLevel::Info => write!(f, "info"),
+            LogLevel::Warn => write!(f, "warn"),
+            LogLevel::Error => write!(f, "error"),
+            LogLevel::Off => write!(f, "off"),
+        }
+    }
+}
+
+impl FromStr for LogLevel {
+    type Err = TrendError;
+
+    fn from_str(s: &str) -> Result<LogLevel> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "trace" => Ok(LogLevel::Trace),
+            "debug" => Ok(LogLevel::Debug),
+            "info" => Ok(LogLevel::Info),
+            "warn" | "warning" => Ok(LogLevel::Warn),
+            "error" => Ok(LogLevel::Error),
+            "off" | "none" => Ok(LogLevel::Off),
+            _ => Err(TrendError::parse("ログレベル", format!("未知のレベルです: {}", s))),
+        }
+    }
+}
+
+/// 1件のログ
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LogRecord {
+    pub level: LogLevel,
+    pub post_id: u32,
+    pub action: String,
+    pub message: String,
+    pub data: serde_json::Value,
+}
+
+/// ログの出力先
+pub trait LogSink {
+    /// ログを1件出力
+    fn write(&self, record: &LogRecord);
+}
+
+/// 何も出力しない出力先
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopSink;
+
+impl LogSink for NoopSink {
+    fn write(&self, _record: &LogRecord) {}
+}
+
+/// コンソール（wasmでは`console.log`、ネイティブでは標準エラー出力）への出力先
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConsoleSink;
+
+impl LogSink for ConsoleSink {
+    fn write(&self, record: &LogRecord) {
+        let line = format!(
+            "[{}] post={} {}: {} {}",
+            record.level, record.post_id, record.action, record.message, record.data
+        );
+        #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+        crate::wasm::console_log(&line);
+        #[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+        eprintln!("{}", line);
+    }
+}
+
+/// 直近のログを保持するリングバッファ（上限を超えると古いものから捨てる）
+#[derive(Debug, Default)]
+pub struct RingBufferSink {
+    capacity: usize,
+    records: RefCell<VecDeque<LogRecord>>,
+}
+
+impl RingBufferSink {
+    /// 保持する件数を指定して作成
+    pub fn new(capacity: usize) -> Self {
+        RingBufferSink {
+            capacity,
+            records: RefCell::new(VecDeque::with_capacity(capacity.min(1024))),
+        }
+    }
+
+    /// 保持しているログ（古い順）
+    pub fn records(&self) -> Vec<LogRecord> {
+        self.records.borrow().iter().cloned().collect()
+    }
+
+    /// 保持しているログを取り出して空にする
+    pub fn drain(&self) -> Vec<LogRecord> {
+        self.records.borrow_mut().drain(..).collect()
+    }
+
+    /// 保持しているログの件数
+    pub fn len(&self) -> usize {
+        self.records.borrow().len()
+    }
+
+    /// ログを保持していないか
+    pub fn is_empty(&self) -> bool {
+        self.records.borrow().is_empty()
+    }
+}
+
+impl LogSink for RingBufferSink {
+    fn write(&self, record: &LogRecord) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut records = self.records.borrow_mut();
+        if records.len() == self.capacity {
+            records.pop_front();
+        }
+        records.push_back(record.clone());
+    }
+}
+
+/// 出力先・レベル・追跡対象の投稿
+struct Logger {
+    sink: Rc<dyn LogSink>,
+    level: LogLevel,
+    trace_all: bool,
+    traced_posts: HashSet<u32>,
+}
+
+thread_local! {
+    static LOGGER: RefCell<Logger> = RefCell::new(Logger {
+        sink: Rc::new(NoopSink),
+        level: LogLevel::default(),
+        trace_all: false,
+        traced_posts: HashSet::new(),
+    });
+}
+
+/// ログの出力先を設定
+pub fn set_log_sink(sink: Rc<dyn LogSink>) {
+    LOGGER.with(|logger| logger.borrow_mut().sink = sink);
+}
+
+/// 出力する最低レベルを設定
+pub fn set_log_level(level: LogLevel) {
+    LOGGER.with(|logger| logger.borrow_mut().level = level);
+}
+
+/// 出力する最低レベルを取得
+pub fn log_level() -> LogLevel {
+    LOGGER.with(|logger| logger.borrow().level)
+}
+
+/// 投稿の計算過程の出力を有効にする
+pub fn trace_post(post_id: u32) {
+    LOGGER.with(|logger| logger.borrow_mut().traced_posts.insert(post_id));
+}
+
+/// 投稿の計算過程の出力を無効にする
+pub fn untrace_post(post_id: u32) {
+    LOGGER.with(|logger| logger.borrow_mut().traced_posts.remove(&post_id));
+}
+
+/// 全投稿の計算過程の出力を切り替える（`false`でも個別に指定した投稿は出力する）
+pub fn trace_all_posts(enabled: bool) {
+    LOGGER.with(|logger| logger.borrow_mut().trace_all = enabled);
+}
+
+/// 出力先・レベル・追跡対象を既定に戻す
+pub fn reset_logging() {
+    LOGGER.with(|logger| {
+        let mut logger = logger.borrow_mut();
+        logger.sink = Rc::new(NoopSink);
+        logger.level = LogLevel::default();
+        logger.trace_all = false;
+        logger.traced_posts.clear();
+    });
+}
+
+/// 指定レベル・投稿のログが出力されるか
+pub fn log_enabled(level: LogLevel, post_id: u32) -> bool {
+    LOGGER.with(|logger| {
+        let logger = logger.borrow();
+        (level != LogLevel::Off && level >= logger.level)
+            || logger.trace_all
+            || logger.traced_posts.contains(&post_id)
+    })
+}
+
+/// ログを出力する（出力しない場合は`data`を呼び出さない）
+pub fn log_event<T: Serialize>(level: LogLevel, post_id: u32, action: &str, message: &str, data: impl FnOnce() -> T) {
+    if !log_enabled(level, post_id) {
+        return;
+    }
+    let record = LogRecord {
+        level,
+        post_id,
+        action: action.to_string(),
+        message: message.to_string(),
+        data: serde_json::to_value(data()).unwrap_or(serde_json::Value::Null),
+    };
+    // 出力先の中でログ設定を変更できるよう、借用を外してから書き込む
+    let sink = LOGGER.with(|logger| Rc::clone(&logger.borrow().sink));
+    sink.write(&record);
+}
+
+/// 投稿ごとの計算過程を出力する（`trace_post`で指定した投稿か、最低レベルが`Trace`の場合のみ）
+pub(crate) fn log_calculation<T: Serialize>(post_id: u32, action: &str, message: &str, data: impl FnOnce() -> T) {
+    log_event(LogLevel::Trace, post_id, action, message, data);
+}
@@ -0,0 +1,333 @@
+//! 閲覧データのバイナリパッキング
+//!
+//! v1・v2 は utils/binaryPacking.js の`BinaryViewPacker`と同じレイアウトで読み書きする。
+//!
+//! v2（10バイト、JSの現行形式）:
+//! - 0〜7バイト目: ビッグエンディアンの64ビット整数
+//!   （投稿ID 24ビット | ユーザーID 24ビット | 2020年1月1日からの経過時間 16ビット）
+//! - 8バイト目: デバイス(上位4ビット) | 国(下位4ビット)
+//! - 9バイト目: 時間内の分 (0〜59)
+//!
+//! v1（9バイト、JSの旧形式）は下位16ビットが分単位の経過時間で、分精度の時刻のみを持つ。
+//!
+//! v2 の経過時間は16ビットのため2027年6月に一周してしまう。v3 ではこれを解消する。
+//!
+//! v3（16バイト）:
+//! - 0バイト目: バージョンタグ (3)
+//! - 1バイト目: デバイス(上位4ビット) | 国(下位4ビット)
+//! - 2〜5バイト目: 投稿ID（32ビット）
+//! - 6〜9バイト目: ユーザーID（32ビット）
+//! - 10〜15バイト目: 閲覧時刻（UNIXミリ秒、48ビット）
+//!
+//! いずれもビッグエンディアン。
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{Result, TrendError};
+
+/// v1（旧形式）のレコード長
+pub const V1_RECORD_LEN: usize = 9;
+/// v2（JSの現行形式）のレコード長
+pub const V2_RECORD_LEN: usize = 10;
+/// v3 のレコード長
+pub const V3_RECORD_LEN: usize = 16;
+/// v3 の先頭バイトに入るバージョンタグ
+pub const V3_VERSION_TAG: u8 = 3;
+
+const MINUTE_MS: u64 = 60 * 1000;
+const HOUR_MS: u64 = 60 * MINUTE_MS;
+/// v2 の基準時刻（2020-01-01T00:00:00Z）
+const BASE_TIME_MS: u64 = 1_577_836_800_000;
+/// v1 で経過分数に加算するオフセット（JS実装の値をそのまま使う）
+const LEGACY_MINUTE_OFFSET: u64 = 26_298_240;
+
+const ID24_MAX: u32 = 0xFF_FFFF;
+const TIMESTAMP48_MAX: u64 = 0xFFFF_FFFF_FFFF;
+const FLAG_MAX: u8 = 0xF;
+
+/// パック形式
+///
+/// JSの`unpack`が返す`format`に合わせ、v1は`"old"`、v2は`"new"`としてシリアライズする。
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PackFormat {
+    /// 9バイトの旧形式
+    #[serde(rename = "old")]
+    V1,
+    /// 10バイトのJS現行形式
+    #[serde(rename = "new")]
+    V2,
+    /// 16バイトのバージョン付き形式
+    #[serde(rename = "v3")]
+    V3,
+}
+
+impl PackFormat {
+    /// 1レコードのバイト数
+    pub fn record_len(&self) -> usize {
+        match self {
+            PackFormat::V1 => V1_RECORD_LEN,
+            PackFormat::V2 => V2_RECORD_LEN,
+            PackFormat::V3 => V3_RECORD_LEN,
+        }
+    }
+
+    /// バッファ長から形式を判定
+    pub fn from_len(len: usize) -> Result<PackFormat> {
+        match len {
+            V1_RECORD_LEN => Ok(PackFormat::V1),
+            V2_RECORD_LEN => Ok(PackFormat::V2),
+            V3_RECORD_LEN => Ok(PackFormat::V3),
+            _ => Err(TrendError::UnknownRecordLength {
+                length: len,
+                accepted: vec![V1_RECORD_LEN, V2_RECORD_LEN, V3_RECORD_LEN],
+            }),
+        }
+    }
+
+    /// 表現できる時刻の範囲（UNIXミリ秒、両端を含む）
+    ///
+    /// v1/v2は分単位に切り捨てて保存するため、最後の分の終わり（59.999秒）までを含む。
+    pub fn valid_time_range(&self) -> (u64, u64) {
+        match self {
+            PackFormat::V1 => {
+                let start = LEGACY_MINUTE_OFFSET * MINUTE_MS;
+                (start, start + 0xFFFF * MINUTE_MS + MINUTE_MS - 1)
+            }
+            PackFormat::V2 => (BASE_TIME_MS, BASE_TIME_MS + 0xFFFF * HOUR_MS + 59 * MINUTE_MS + MINUTE_MS - 1),
+            PackFormat::V3 => (0, TIMESTAMP48_MAX),
+        }
+    }
+}
+
+impl fmt::Display for PackFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PackFormat::V1 => write!(f, "old"),
+            PackFormat::V2 => write!(f, "new"),
+            PackFormat::V3 => write!(f, "v3"),
+        }
+    }
+}
+
+impl FromStr for PackFormat {
+    type Err = TrendError;
+
+    fn from_str(s: &str) -> Result<PackFormat> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "old" | "v1" | "1" => Ok(PackFormat::V1),
+            "new" | "v2" | "2" => Ok(PackFormat::V2),
+            "v3" | "3" => Ok(PackFormat::V3),
+            _ => Err(TrendError::parse("パック形式", format!("未知の形式です: {}", s))),
+        }
+    }
+}
+
+/// 1件の閲覧レコード
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ViewRecord {
+    pub post_id: u32,   // 投稿ID（v1・v2は24ビット）
+    pub user_id: u32,   // ユーザーID（v1・v2は24ビット）
+    pub timestamp: u64, // 閲覧時刻（UNIXミリ秒、v1・v2は分未満を切り捨て）
+    #[serde(default)]
+    pub device: u8,     // デバイスコード (4ビット)
+    #[serde(default)]
+    pub country: u8,    // 国コード (4ビット)
+}
+
+/// 復元した閲覧レコードと元の形式
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodedView {
+    #[serde(flatten)]
+    pub record: ViewRecord,
+    pub format: PackFormat,
+}
+
+/// 閲覧データのエンコーダー・デコーダー
+pub struct BinaryViewPacker;
+
+impl BinaryViewPacker {
+    /// 閲覧レコードをJS互換の10バイト形式 (v2) にパックする
+    ///
+    /// JS実装はビット幅を超えた値を黙って切り捨てるが、ここではエラーにする。
+    pub fn pack(record: &ViewRecord) -> Result<[u8; V2_RECORD_LEN]> {
+        check_range("post_id", record.post_id as u64, ID24_MAX as u64)?;
+        check_range("user_id", record.user_id as u64, ID24_MAX as u64)?;
+        check_flags(record)?;
+        check_timestamp(record, PackFormat::V2)?;
+
+        let hours_since_base = (record.timestamp - BASE_TIME_MS) / HOUR_MS;
+        let minutes_in_hour = (record.timestamp % HOUR_MS) / MINUTE_MS;
+
+        let packed = (record.post_id as u64) << 40 | (record.user_id as u64) << 16 | hours_since_base;
+
+        let mut buffer = [0u8; V2_RECORD_LEN];
+        buffer[..8].copy_from_slice(&packed.to_be_bytes());
+        buffer[8] = record.device << 4 | record.country;
+        buffer[9] = minutes_in_hour as u8;
+        Ok(buffer)
+    }
+
+    /// 閲覧レコードを16バイトの v3 形式にパックする
+    pub fn pack_v3(record: &ViewRecord) -> Result<[u8; V3_RECORD_LEN]> {
+        check_flags(record)?;
+        check_timestamp(record, PackFormat::V3)?;
+
+        let mut buffer = [0u8; V3_RECORD_LEN];
+        buffer[0] = V3_VERSION_TAG;
+        buffer[1] = record.device << 4 | record.country;
+        buffer[2..6].copy_from_slice(&record.post_id.to_be_bytes());
+        buffer[6..10].copy_from_slice(&record.user_id.to_be_bytes());
+        buffer[10..].copy_from_slice(&record.timestamp.to_be_bytes()[2..]);
+        Ok(buffer)
+    }
+
+    /// 複数のレコードを指定した形式で連結したバッファにパックする（v1への書き込みは不可）
+    pub fn pack_all(records: &[ViewRecord], format: PackFormat) -> Result<Vec<u8>> {
+        let mut buffer = Vec::with_capacity(records.len() * format.record_len());
+        for record in records {
+            match format {
+                PackFormat::V1 => {
+                    return Err(TrendError::parse("パック形式", "v1形式への書き込みはサポートしていません"))
+                }
+                PackFormat::V2 => buffer.extend_from_slice(&Self::pack(record)?),
+                PackFormat::V3 => buffer.extend_from_slice(&Self::pack_v3(record)?),
+            }
+        }
+        Ok(buffer)
+    }
+
+    /// 1件分のバッファを復元する（形式はバッファ長で判定）
+    pub fn unpack(buffer: &[u8]) -> Result<DecodedView> {
+        Self::unpack_as(buffer, PackFormat::from_len(buffer.len())?)
+    }
+
+    /// 同じ形式のレコードを連結したバッファを一括で復元する
+    pub fn unpack_all(buffer: &[u8], format: PackFormat) -> Result<Vec<DecodedView>> {
+        let record_len = format.record_len();
+        if !buffer.len().is_multiple_of(record_len) {
+            return Err(TrendError::InvalidBufferLength {
+                length: buffer.len(),
+                record_len,
+            });
+        }
+
+        buffer
+            .chunks_exact(record_len)
+            .map(|chunk| Self::unpack_as(chunk, format))
+            .collect()
+    }
+
+    /// 1件分のバッファを v3 形式に書き換える（v3 はそのまま検証して返す）
+    ///
+    /// v2 の経過時間は一周目（2020年1月〜2027年6月）として解釈する。
+    pub fn migrate(buffer: &[u8]) -> Result<[u8; V3_RECORD_LEN]> {
+        Self::pack_v3(&Self::unpack(buffer)?.record)
+    }
+
+    /// 同じ形式のレコードを連結したバッファを v3 形式に一括で書き換える
+    pub fn migrate_all(buffer: &[u8], from: PackFormat) -> Result<Vec<u8>> {
+        let records: Vec<ViewRecord> = Self::unpack_all(buffer, from)?
+            .into_iter()
+            .map(|decoded| decoded.record)
+            .collect();
+        Self::pack_all(&records, PackFormat::V3)
+    }
+
+    fn unpack_as(buffer: &[u8], format: PackFormat) -> Result<DecodedView> {
+        if buffer.len() != format.record_len() {
+            return Err(TrendError::InvalidBufferLength {
+                length: buffer.len(),
+                record_len: format.record_len(),
+            });
+        }
+
+        let record = match format {
+            PackFormat::V1 | PackFormat::V2 => unpack_legacy(buffer, format)?,
+            PackFormat::V3 => unpack_v3(buffer)?,
+        };
+        Ok(DecodedView { record, format })
+    }
+}
+
+/// v1・v2 のレコードを復元
+fn unpack_legacy(buffer: &[u8], format: PackFormat) -> Result<ViewRecord> {
+    let packed = u64::from_be_bytes(read_bytes(&buffer[..8]));
+    let flags = buffer[8];
+
+    let time_part = packed & 0xFFFF;
+    let timestamp = if format == PackFormat::V1 {
+        (time_part + LEGACY_MINUTE_OFFSET) * MINUTE_MS
+    } else {
+        let minutes_in_hour = buffer[9] as u64;
+        if minutes_in_hour >= 60 {
+            return Err(TrendError::InvalidViewRecord {
+                field: "minutes_in_hour".to_string(),
+                value: minutes_in_hour,
+            });
+        }
+        BASE_TIME_MS + time_part * HOUR_MS + minutes_in_hour * MINUTE_MS
+    };
+
+    Ok(ViewRecord {
+        post_id: ((packed >> 40) & ID24_MAX as u64) as u32,
+        user_id: ((packed >> 16) & ID24_MAX as u64) as u32,
+        timestamp,
+        device: flags >> 4,
+        country: flags & FLAG_MAX,
+    })
+}
+
+/// v3 のレコードを復元
+fn unpack_v3(buffer: &[u8]) -> Result<ViewRecord> {
+    if buffer[0] != V3_VERSION_TAG {
+        return Err(TrendError::InvalidViewRecord {
+            field: "version".to_string(),
+            value: buffer[0] as u64,
+        });
+    }
+
+    let mut timestamp = [0u8; 8];
+    timestamp[2..].copy_from_slice(&buffer[10..16]);
+
+    Ok(ViewRecord {
+        post_id: u32::from_be_bytes(read_bytes(&buffer[2..6])),
+        user_id: u32::from_be_bytes(read_bytes(&buffer[6..10])),
+        timestamp: u64::from_be_bytes(timestamp),
+        device: buffer[1] >> 4,
+        country: buffer[1] & FLAG_MAX,
+    })
+}
+
+fn read_bytes<const N: usize>(bytes: &[u8]) -> [u8; N] {
+    let mut array = [0u8; N];
+    array.copy_from_slice(bytes);
+    array
+}
+
+fn check_flags(record: &ViewRecord) -> Result<()> {
+    check_range("device", record.device as u64, FLAG_MAX as u64)?;
+    check_range("country", record.country as u64, FLAG_MAX as u64)
+}
+
+fn check_timestamp(record: &ViewRecord, format: PackFormat) -> Result<()> {
+    let (start, end) = format.valid_time_range();
+    if record.timestamp < start || record.timestamp > end {
+        return Err(TrendError::InvalidViewRecord {
+            field: "timestamp".to_string(),
+            value: record.timestamp,
+        });
+    }
+    Ok(())
+}
+
+fn check_range(field: &str, value: u64, max: u64) -> Result<()> {
+    if value > max {
+        return Err(TrendError::InvalidViewRecord {
+            field: field.to_string(),
+            value,
+        });
+    }
+    Ok(())
+}
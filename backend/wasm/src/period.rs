@@ -0,0 +1,178 @@
+//! ランキングの集計期間
+//!
+//! JS側では`'daily'`と`'day'`の両方の表記が使われているため、どちらからでも解析できる。
+//! 任意の長さの期間（"last 6 hours"、"3d"など）も指定でき、
+//! パラメータはその長さを収められる最も短い標準期間のものを使う（"last 25 hours"なら週次）。
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{Result, TrendError};
+
+const HOUR_MS: u64 = 60 * 60 * 1000;
+const DAY_MS: u64 = 24 * HOUR_MS;
+
+/// ランキングの集計期間
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(try_from = "String", into = "String")]
+pub enum Period {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+    /// 任意の長さの期間（ミリ秒）
+    Custom { duration_ms: u64 },
+}
+
+impl Period {
+    /// 標準の4期間
+    pub const STANDARD: [Period; 4] = [Period::Daily, Period::Weekly, Period::Monthly, Period::Yearly];
+
+    /// 従来の期間タイプ (0: 日次, 1: 週次, 2: 月次, 3: 年次) から変換
+    pub fn from_type(period_type: u8) -> Result<Period> {
+        match period_type {
+            0 => Ok(Period::Daily),
+            1 => Ok(Period::Weekly),
+            2 => Ok(Period::Monthly),
+            3 => Ok(Period::Yearly),
+            _ => Err(TrendError::InvalidPeriod {
+                value: period_type.to_string(),
+            }),
+        }
+    }
+
+    /// 時間数を指定して任意の期間を作成
+    pub fn last_hours(hours: u64) -> Result<Period> {
+        Self::custom(hours.checked_mul(HOUR_MS), &format!("last {} hours", hours))
+    }
+
+    /// 日数を指定して任意の期間を作成
+    pub fn last_days(days: u64) -> Result<Period> {
+        Self::custom(days.checked_mul(DAY_MS), &format!("last {} days", days))
+    }
+
+    fn custom(duration_ms: Option<u64>, label: &str) -> Result<Period> {
+        match duration_ms {
+            Some(duration_ms) if duration_ms > 0 => Ok(Period::Custom { duration_ms }),
+            _ => Err(TrendError::InvalidPeriod {
+                value: label.to_string(),
+            }),
+        }
+    }
+
+    /// 期間の長さ（ミリ秒）
+    pub fn duration_ms(&self) -> u64 {
+        match self {
+            Period::Daily => DAY_MS,
+            Period::Weekly => 7 * DAY_MS,
+            Period::Monthly => 30 * DAY_MS,
+            Period::Yearly => 365 * DAY_MS,
+            Period::Custom { duration_ms } => *duration_ms,
+        }
+    }
+
+    /// 期間の長さ（時間）
+    pub fn hours(&self) -> f64 {
+        self.duration_ms() as f64 / HOUR_MS as f64
+    }
+
+    /// パラメータテーブルを引くための標準期間
+    ///
+    /// 任意の期間は、その長さを収められる最も短い標準期間として扱う。
+    pub fn base(&self) -> Period {
+        match *self {
+            Period::Custom { duration_ms } => {
+                if duration_ms <= DAY_MS {
+                    Period::Daily
+                } else if duration_ms <= 7 * DAY_MS {
+                    Period::Weekly
+                } else if duration_ms <= 30 * DAY_MS {
+                    Period::Monthly
+                } else {
+                    Period::Yearly
+                }
+            }
+            standard => standard,
+        }
+    }
+
+    /// 従来の期間タイプの番号（任意の期間は標準期間の番号）
+    pub fn type_index(&self) -> u8 {
+        match self.base() {
+            Period::Weekly => 1,
+            Period::Monthly => 2,
+            Period::Yearly => 3,
+            _ => 0,
+        }
+    }
+}
+
+impl fmt::Display for Period {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Period::Daily => write!(f, "daily"),
+            Period::Weekly => write!(f, "weekly"),
+            Period::Monthly => write!(f, "monthly"),
+            Period::Yearly => write!(f, "yearly"),
+            Period::Custom { duration_ms } => {
+                if duration_ms % DAY_MS == 0 {
+                    write!(f, "last {} days", duration_ms / DAY_MS)
+                } else if duration_ms % HOUR_MS == 0 {
+                    write!(f, "last {} hours", duration_ms / HOUR_MS)
+                } else {
+                    write!(f, "last {} ms", duration_ms)
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for Period {
+    type Err = TrendError;
+
+    /// 次の形式を解析する
+    ///
+    /// - `daily` / `day` / `0`（週次・月次・年次も同様）
+    /// - `last 6 hours` / `6 hours` / `6h`
+    /// - `last 3 days` / `3 days` / `3d`
+    fn from_str(s: &str) -> Result<Period> {
+        let invalid = || TrendError::InvalidPeriod { value: s.to_string() };
+        let normalized = s.trim().to_ascii_lowercase();
+
+        match normalized.as_str() {
+            "daily" | "day" | "0" => return Ok(Period::Daily),
+            "weekly" | "week" | "1" => return Ok(Period::Weekly),
+            "monthly" | "month" | "2" => return Ok(Period::Monthly),
+            "yearly" | "year" | "3" => return Ok(Period::Yearly),
+            _ => {}
+        }
+
+        let spec = normalized.strip_prefix("last").unwrap_or(&normalized).trim();
+        let split = spec.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+        let (amount, unit) = spec.split_at(split);
+        let amount: u64 = amount.parse().map_err(|_| invalid())?;
+
+        match unit.trim() {
+            "h" | "hour" | "hours" => Period::last_hours(amount),
+            "d" | "day" | "days" => Period::last_days(amount),
+            "ms" => Period::custom(Some(amount), s),
+            _ => Err(invalid()),
+        }
+        .map_err(|_| invalid())
+    }
+}
+
+impl TryFrom<String> for Period {
+    type Error = TrendError;
+
+    fn try_from(value: String) -> Result<Period> {
+        value.parse()
+    }
+}
+
+impl From<Period> for String {
+    fn from(period: Period) -> String {
+        period.to_string()
+    }
+}
@@ -0,0 +1,87 @@
+//! ランキングの並び順と同点時の順位決定
+//!
+//! スコアそのものには補正を加えず、同点の場合は次の順で順位を決める。
+//!
+//! 1. スコアの降順（NaNは最下位、-0.0は0.0と同点として扱う）
+//! 2. ユニークユーザー数の降順
+//! 3. 最終アクティビティ時刻の降順（新しい方が上位）
+//! 4. 投稿IDの昇順
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+/// ランキングの並び替えに使うキー
+///
+/// `Ord`の実装が順位決定ポリシーそのもので、`Less`が上位を表す。
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct RankingKey {
+    pub post_id: u32,
+    pub score: f64,
+    pub unique_users: u32,
+    pub last_activity: u64, // 最終アクティビティ時刻（UNIXミリ秒）
+}
+
+impl RankingKey {
+    /// 同点時の順位決定ポリシーに従って比較（上位が`Less`）
+    pub fn ranking_cmp(&self, other: &Self) -> Ordering {
+        self.score
+            .is_nan()
+            .cmp(&other.score.is_nan())
+            .then_with(|| comparable_score(other.score).total_cmp(&comparable_score(self.score)))
+            .then_with(|| other.unique_users.cmp(&self.unique_users))
+            .then_with(|| other.last_activity.cmp(&self.last_activity))
+            .then_with(|| self.post_id.cmp(&other.post_id))
+    }
+}
+
+impl PartialEq for RankingKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for RankingKey {}
+
+impl PartialOrd for RankingKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for RankingKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.ranking_cmp(other)
+    }
+}
+
+/// 比較に使うスコア（NaN同士は同点、`-0.0 + 0.0`は`0.0`になる）
+fn comparable_score(score: f64) -> f64 {
+    if score.is_nan() {
+        0.0
+    } else {
+        score + 0.0
+    }
+}
+
+/// ランキング上の1件
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RankedPost {
+    pub rank: u32,
+    pub post_id: u32,
+    pub score: f64,
+}
+
+/// RankingKeyの順位決定ポリシーに従って上位N件を抽出
+pub fn rank_top_n(mut keys: Vec<RankingKey>, top_n: usize) -> Vec<RankedPost> {
+    keys.sort_unstable();
+
+    keys.into_iter()
+        .take(top_n)
+        .enumerate()
+        .map(|(i, key)| RankedPost {
+            rank: i as u32 + 1,
+            post_id: key.post_id,
+            score: key.score,
+        })
+        .collect()
+}
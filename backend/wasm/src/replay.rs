@@ -0,0 +1,263 @@
+//! スコア設定の変更によるランキングの変化の再現（オフライン）
+//!
+//! 投稿ごとの入力のスナップショット（JSON Lines）を2つの設定で計算し、
+//! 順位相関（ケンドールのτ・スピアマンのρ）、上位N件への出入り、順位の変動が大きい投稿を求める。
+//! `src/bin/replay.rs`のコマンドから使う。
+//!
+//! スナップショットの1行は1投稿で、`direct`があれば直接計算、`hll`があればRedis HLLデータから計算、
+//! どちらもなければ`windows`・`events`から`calculate_trend_score`と同じ計算を行う。
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::batch::BatchError;
+use crate::config::ScoringConfig;
+use crate::engine::PeriodRanking;
+use crate::error::{Result, TrendError};
+use crate::period::Period;
+use crate::ranking::{rank_top_n, RankedPost, RankingKey};
+use crate::trend_calculator::{DirectCalculationData, RedisHllData, TrendCalculator, ViewEvent, WindowMetrics};
+
+/// スナップショットの1投稿分の入力
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SnapshotPost {
+    pub post_id: u32,
+    #[serde(default)]
+    pub windows: Vec<WindowMetrics>,
+    #[serde(default)]
+    pub events: Vec<ViewEvent>,
+    #[serde(default)]
+    pub direct: Option<DirectCalculationData>,
+    #[serde(default)]
+    pub hll: Option<RedisHllData>,
+    #[serde(default)]
+    pub suspicion: f64,
+}
+
+/// 1投稿の順位の変化
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RankChange {
+    pub post_id: u32,
+    pub baseline_rank: Option<u32>, // 計算に失敗した場合はNone
+    pub candidate_rank: Option<u32>,
+    pub baseline_score: Option<f64>,
+    pub candidate_score: Option<f64>,
+    pub rank_change: i64, // 上がった順位数（下がった場合は負、片方にしかない場合は0）
+}
+
+/// 2つの設定のランキングの比較結果
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ReplayReport {
+    pub compared_posts: usize, // 両方の設定で計算できた投稿数
+    pub kendall_tau: f64,
+    pub spearman_rho: f64,
+    pub top_n: usize,
+    pub top_n_overlap: usize,     // 両方の上位N件に入った投稿数
+    pub entered: Vec<RankChange>, // 変更後に上位N件に入った投稿（変更後の順位順）
+    pub exited: Vec<RankChange>,  // 変更後に上位N件から外れた投稿（変更前の順位順）
+    pub movers: Vec<RankChange>,  // どちらかの上位N件に入った投稿のうち、順位の変動が大きい順
+    pub baseline_errors: usize,
+    pub candidate_errors: usize,
+}
+
+/// JSON Lines形式のスナップショットを読み込む（空行は無視）
+pub fn parse_snapshot(text: &str) -> Result<Vec<SnapshotPost>> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line)
+                .map_err(|e| TrendError::parse("スナップショット", format!("{}行目: {}", index + 1, e)))
+        })
+        .collect()
+}
+
+/// 指定時刻（UNIXミリ秒）を現在時刻として、スナップショットの全投稿の順位を求める
+///
+/// 個別の投稿の計算エラーは`errors`に記録する。
+pub fn replay_snapshot(posts: &[SnapshotPost], config: Arc<ScoringConfig>, period: Period, now: u64) -> PeriodRanking {
+    let mut calculator = TrendCalculator::new(0, period).with_config(config);
+    let mut keys = Vec::with_capacity(posts.len());
+    let mut errors = Vec::new();
+
+    for post in posts {
+        calculator.set_post_id(post.post_id);
+        calculator.set_suspicion(post.suspicion);
+        let scored = if let Some(data) = &post.direct {
+            calculator
+                .calculate_trending_score_direct_at(data, now)
+                .map(|r| (r.score, data.unique_users, data.last_updated))
+        } else if let Some(data) = &post.hll {
+            calculator
+                .calculate_with_redis_hll_data_at(data, now)
+                .map(|s| (s.score, data.unique_users, data.last_activity_time))
+        } else {
+            let last_activity = post
+                .events
+                .iter()
+                .map(|e| e.timestamp)
+                .chain(post.windows.iter().map(|w| w.end_time))
+                .max()
+                .unwrap_or(0);
+            calculator
+                .score_events_at(&post.events, &post.windows, now)
+                .map(|s| (s.score, s.unique_users, last_activity))
+        };
+
+        match scored {
+            Ok((score, unique_users, last_activity)) => keys.push(RankingKey {
+                post_id: post.post_id,
+                score,
+                unique_users,
+                last_activity,
+            }),
+            Err(error) => errors.push(BatchError {
+                post_id: post.post_id,
+                error,
+            }),
+        }
+    }
+
+    PeriodRanking {
+        period,
+        ranking: rank_top_n(keys, posts.len()),
+        errors,
+    }
+}
+
+/// 2つの設定の全投稿の順位を比べる
+///
+/// 順位相関は両方で計算できた投稿の順位で求める（2件未満なら1.0）。
+/// `movers`件まで、どちらかの上位`top_n`件に入った投稿を順位の変動の大きい順に返す。
+pub fn compare_rankings(baseline: &PeriodRanking, candidate: &PeriodRanking, top_n: usize, movers: usize) -> ReplayReport {
+    let candidate_by_post: HashMap<u32, &RankedPost> = candidate.ranking.iter().map(|r| (r.post_id, r)).collect();
+    let baseline_by_post: HashMap<u32, &RankedPost> = baseline.ranking.iter().map(|r| (r.post_id, r)).collect();
+
+    let change = |post_id: u32| {
+        let before = baseline_by_post.get(&post_id);
+        let after = candidate_by_post.get(&post_id);
+        RankChange {
+            post_id,
+            baseline_rank: before.map(|r| r.rank),
+            candidate_rank: after.map(|r| r.rank),
+            baseline_score: before.map(|r| r.score),
+            candidate_score: after.map(|r| r.score),
+            rank_change: match (before, after) {
+                (Some(before), Some(after)) => before.rank as i64 - after.rank as i64,
+                _ => 0,
+            },
+        }
+    };
+    let in_top = |rank: Option<u32>| rank.is_some_and(|rank| rank as usize <= top_n);
+
+    // 変更前の順位順に並べた、両方で計算できた投稿の変更後の順位
+    let candidate_ranks: Vec<u32> = baseline
+        .ranking
+        .iter()
+        .filter_map(|r| candidate_by_post.get(&r.post_id).map(|c| c.rank))
+        .collect();
+
+    let entered: Vec<RankChange> = candidate
+        .ranking
+        .iter()
+        .take(top_n)
+        .filter(|r| !in_top(baseline_by_post.get(&r.post_id).map(|b| b.rank)))
+        .map(|r| change(r.post_id))
+        .collect();
+    let exited: Vec<RankChange> = baseline
+        .ranking
+        .iter()
+        .take(top_n)
+        .filter(|r| !in_top(candidate_by_post.get(&r.post_id).map(|c| c.rank)))
+        .map(|r| change(r.post_id))
+        .collect();
+
+    let mut top_movers: Vec<RankChange> = baseline
+        .ranking
+        .iter()
+        .take(top_n)
+        .chain(candidate.ranking.iter().take(top_n).filter(|r| !in_top(baseline_by_post.get(&r.post_id).map(|b| b.rank))))
+        .map(|r| change(r.post_id))
+        .filter(|c| c.rank_change != 0)
+        .collect();
+    top_movers.sort_by(|a, b| b.rank_change.abs().cmp(&a.rank_change.abs()).then_with(|| a.post_id.cmp(&b.post_id)));
+    top_movers.truncate(movers);
+
+    ReplayReport {
+        compared_posts: candidate_ranks.len(),
+        kendall_tau: kendall_tau(&candidate_ranks),
+        spearman_rho: spearman_rho(&candidate_ranks),
+        top_n,
+        top_n_overlap: baseline.ranking.len().min(top_n) - exited.len(),
+        entered,
+        exited,
+        movers: top_movers,
+        baseline_errors: baseline.errors.len(),
+        candidate_errors: candidate.errors.len(),
+    }
+}
+
+/// 変更前の順位順に並べた変更後の順位（重複なし）のケンドールのτ
+///
+/// 不一致の組の数は反転数なので、マージソートでO(n log n)で数える。
+fn kendall_tau(ranks: &[u32]) -> f64 {
+    let n = ranks.len();
+    if n < 2 {
+        return 1.0;
+    }
+    let mut values = ranks.to_vec();
+    let mut buffer = vec![0; n];
+    let discordant = count_inversions(&mut values, &mut buffer) as f64;
+    let pairs = (n * (n - 1) / 2) as f64;
+    1.0 - 2.0 * discordant / pairs
+}
+
+/// 並べ替えながら反転数を数える
+fn count_inversions(values: &mut [u32], buffer: &mut [u32]) -> u64 {
+    let n = values.len();
+    if n < 2 {
+        return 0;
+    }
+    let mid = n / 2;
+    let mut inversions = {
+        let (left, right) = values.split_at_mut(mid);
+        count_inversions(left, &mut buffer[..mid]) + count_inversions(right, &mut buffer[mid..])
+    };
+
+    let (mut i, mut j) = (0, mid);
+    for slot in buffer[..n].iter_mut() {
+        if j >= n || (i < mid && values[i] <= values[j]) {
+            *slot = values[i];
+            i += 1;
+        } else {
+            *slot = values[j];
+            inversions += (mid - i) as u64;
+            j += 1;
+        }
+    }
+    values.copy_from_slice(&buffer[..n]);
+    inversions
+}
+
+/// 変更前の順位順に並べた変更後の順位（重複なし）のスピアマンのρ
+///
+/// 変更後の順位は両方で計算できた投稿の中での順位に付け直す。
+fn spearman_rho(ranks: &[u32]) -> f64 {
+    let n = ranks.len();
+    if n < 2 {
+        return 1.0;
+    }
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by_key(|&i| ranks[i]);
+    let sum_squares: f64 = order
+        .iter()
+        .enumerate()
+        .map(|(candidate_position, &baseline_position)| {
+            let d = candidate_position as f64 - baseline_position as f64;
+            d * d
+        })
+        .sum();
+    let n = n as f64;
+    1.0 - 6.0 * sum_squares / (n * (n * n - 1.0))
+}
@@ -0,0 +1,291 @@
+//! 時間窓の段階集約（hour → day → week → month → year）
+//!
+//! viewAggregate.js の`aggregateToHour`〜`aggregateToYear`と同じ暦の規則で集約する。
+//!
+//! - 窓の境界は日本時間 (UTC+9) で決める
+//! - 週は月曜始まり（`getGroupStartTimeOptimized`と同じ）
+//! - 上位の窓へは、下位の窓の開始時刻が属する窓に振り分ける
+//!   （月をまたぐ週は開始日の月に入る）
+//! - 窓の終了時刻は次の窓の開始時刻の1ミリ秒前
+//!
+//! 窓は投稿ごとに作る（JSと同じく、投稿IDと開始時刻の組で1つの窓）。
+//!
+//! 時間単位の窓にはユニークユーザーのHyperLogLogを持たせ、上位の窓ではその和集合から
+//! ユニークユーザー数を求める。スケッチのない窓が混ざる場合は下位の窓の最大値とする。
+//! JSは新規作成時には下位の窓の和（重複を数える）、既存の窓の再集約時には最大値を使うため、
+//! 新規作成した窓の値はJSと一致しない。いずれも何度実行しても同じ結果になる。
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{Result, TrendError};
+use crate::hyperloglog::HyperLogLog;
+use crate::packing::ViewRecord;
+use crate::trend_calculator::{Metrics, WindowMetrics};
+
+const HOUR_MS: u64 = 60 * 60 * 1000;
+const DAY_MS: u64 = 24 * HOUR_MS;
+/// 日本時間のUTCからのオフセット
+const JST_OFFSET_MS: u64 = 9 * HOUR_MS;
+
+/// 集約の単位
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum RollupLevel {
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl RollupLevel {
+    /// 集約元となる1段下の単位
+    pub fn source(&self) -> Option<RollupLevel> {
+        match self {
+            RollupLevel::Hour => None,
+            RollupLevel::Day => Some(RollupLevel::Hour),
+            RollupLevel::Week => Some(RollupLevel::Day),
+            RollupLevel::Month => Some(RollupLevel::Week),
+            RollupLevel::Year => Some(RollupLevel::Month),
+        }
+    }
+
+    /// 指定時刻を含む窓の開始時刻（UNIXミリ秒）
+    pub fn window_start(&self, timestamp: u64) -> u64 {
+        match self {
+            RollupLevel::Hour => timestamp - timestamp % HOUR_MS,
+            _ => {
+                let local_day = (timestamp.saturating_add(JST_OFFSET_MS) / DAY_MS) as i64;
+                let start_day = match self {
+                    RollupLevel::Week => local_day - (local_day + 3).rem_euclid(7),
+                    RollupLevel::Month => {
+                        let (year, month, _) = civil_from_days(local_day);
+                        days_from_civil(year, month, 1)
+                    }
+                    RollupLevel::Year => {
+                        let (year, _, _) = civil_from_days(local_day);
+                        days_from_civil(year, 1, 1)
+                    }
+                    _ => local_day,
+                };
+                from_local_day(start_day)
+            }
+        }
+    }
+
+    /// 指定時刻を含む窓の終了時刻（UNIXミリ秒、窓に含まれる最後の時刻）
+    pub fn window_end(&self, timestamp: u64) -> u64 {
+        let start = self.window_start(timestamp);
+        let next_start = match self {
+            RollupLevel::Hour => start + HOUR_MS,
+            RollupLevel::Day => start + DAY_MS,
+            RollupLevel::Week => start + 7 * DAY_MS,
+            RollupLevel::Month | RollupLevel::Year => {
+                let (year, month, _) = civil_from_days(((start + JST_OFFSET_MS) / DAY_MS) as i64);
+                let next_day = if *self == RollupLevel::Year || month == 12 {
+                    days_from_civil(year + 1, 1, 1)
+                } else {
+                    days_from_civil(year, month + 1, 1)
+                };
+                from_local_day(next_day)
+            }
+        };
+        next_start - 1
+    }
+}
+
+impl fmt::Display for RollupLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RollupLevel::Hour => write!(f, "hour"),
+            RollupLevel::Day => write!(f, "day"),
+            RollupLevel::Week => write!(f, "week"),
+            RollupLevel::Month => write!(f, "month"),
+            RollupLevel::Year => write!(f, "year"),
+        }
+    }
+}
+
+impl FromStr for RollupLevel {
+    type Err = TrendError;
+
+    fn from_str(s: &str) -> Result<RollupLevel> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "hour" => Ok(RollupLevel::Hour),
+            "day" => Ok(RollupLevel::Day),
+            "week" => Ok(RollupLevel::Week),
+            "month" => Ok(RollupLevel::Month),
+            "year" => Ok(RollupLevel::Year),
+            _ => Err(TrendError::parse("集約単位", format!("未知の単位です: {}", s))),
+        }
+    }
+}
+
+/// 集約単位付きの時間窓
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RollupWindow {
+    #[serde(default)]
+    pub post_id: u32,
+    pub level: RollupLevel,
+    #[serde(flatten)]
+    pub window: WindowMetrics,
+    #[serde(default)]
+    pub source_count: u32, // 集約元の窓（hourはイベント）の数
+}
+
+/// 既存の窓と集約結果の差分
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct RollupDiff {
+    pub created: Vec<RollupWindow>, // 新規に作成する窓
+    pub updated: Vec<RollupWindow>, // 値が変わった窓
+    pub unchanged: u32,             // 書き換え不要な窓の数
+}
+
+/// 閲覧イベントを投稿ごとに時間単位の窓に集約（投稿ID・開始時刻の順）
+pub fn rollup_events(events: &[ViewRecord]) -> Vec<RollupWindow> {
+    let mut groups: BTreeMap<(u32, u64), (HashSet<u32>, HyperLogLog, u32)> = BTreeMap::new();
+    for event in events {
+        let start = RollupLevel::Hour.window_start(event.timestamp);
+        let (users, sketch, views) = groups.entry((event.post_id, start)).or_default();
+        if users.insert(event.user_id) {
+            sketch.add_user(event.user_id);
+        }
+        *views += 1;
+    }
+
+    groups
+        .into_iter()
+        .map(|((post_id, start), (users, sketch, views))| RollupWindow {
+            post_id,
+            level: RollupLevel::Hour,
+            window: WindowMetrics {
+                start_time: start,
+                end_time: RollupLevel::Hour.window_end(start),
+                metrics: Metrics {
+                    unique_users: users.len() as u32,
+                    total_views: views,
+                },
+                sketch: Some(sketch),
+            },
+            source_count: views,
+        })
+        .collect()
+}
+
+/// 1段下の単位の窓を投稿ごとに`to`の単位に集約（他の単位の窓は無視する）
+pub fn rollup_windows(sources: &[RollupWindow], to: RollupLevel) -> Vec<RollupWindow> {
+    let Some(from) = to.source() else {
+        return Vec::new();
+    };
+
+    let mut groups: BTreeMap<(u32, u64), RollupGroup> = BTreeMap::new();
+    for source in sources.iter().filter(|w| w.level == from) {
+        let start = to.window_start(source.window.start_time);
+        let group = groups.entry((source.post_id, start)).or_insert_with(|| RollupGroup {
+            sketch: Some(HyperLogLog::new()),
+            ..RollupGroup::default()
+        });
+        group.metrics.unique_users = group.metrics.unique_users.max(source.window.metrics.unique_users);
+        group.metrics.total_views = group.metrics.total_views.saturating_add(source.window.metrics.total_views);
+        group.sketch = match (group.sketch.take(), &source.window.sketch) {
+            (Some(mut union), Some(sketch)) => {
+                union.merge(sketch);
+                Some(union)
+            }
+            _ => None,
+        };
+        group.source_count += 1;
+    }
+
+    groups
+        .into_iter()
+        .map(|((post_id, start), mut group)| {
+            if let Some(union) = &group.sketch {
+                group.metrics.unique_users = union.count().min(u32::MAX as u64) as u32;
+            }
+            RollupWindow {
+                post_id,
+                level: to,
+                window: WindowMetrics {
+                    start_time: start,
+                    end_time: to.window_end(start),
+                    metrics: group.metrics,
+                    sketch: group.sketch,
+                },
+                source_count: group.source_count,
+            }
+        })
+        .collect()
+}
+
+/// 集約中の上位の窓
+#[derive(Default)]
+struct RollupGroup {
+    metrics: Metrics,
+    sketch: Option<HyperLogLog>, // 集約元の全てにスケッチがある場合のみ和集合を持つ
+    source_count: u32,
+}
+
+/// 時間単位の窓から day・week・month・year の窓を順に集約
+pub fn rollup_all(hours: &[RollupWindow]) -> Vec<RollupWindow> {
+    let mut all: Vec<RollupWindow> = hours.iter().filter(|w| w.level == RollupLevel::Hour).cloned().collect();
+    let mut current = all.clone();
+    for level in [RollupLevel::Day, RollupLevel::Week, RollupLevel::Month, RollupLevel::Year] {
+        current = rollup_windows(&current, level);
+        all.extend(current.iter().cloned());
+    }
+    all
+}
+
+/// 集約結果を既存の窓と比較し、作成・更新が必要な窓だけを返す
+///
+/// 窓は投稿ID・単位・開始時刻で対応付け、値か集約元の数が変わった場合のみ更新とする。
+pub fn diff_windows(existing: &[RollupWindow], computed: &[RollupWindow]) -> RollupDiff {
+    let index: BTreeMap<(u32, RollupLevel, u64), &RollupWindow> = existing
+        .iter()
+        .map(|w| ((w.post_id, w.level, w.window.start_time), w))
+        .collect();
+
+    let mut diff = RollupDiff::default();
+    for window in computed {
+        match index.get(&(window.post_id, window.level, window.window.start_time)) {
+            None => diff.created.push(window.clone()),
+            Some(current) if *current != window => diff.updated.push(window.clone()),
+            Some(_) => diff.unchanged += 1,
+        }
+    }
+    diff
+}
+
+/// 日本時間の日番号（1970-01-01からの日数）の0時をUNIXミリ秒に変換
+fn from_local_day(day: i64) -> u64 {
+    (day * DAY_MS as i64 - JST_OFFSET_MS as i64).max(0) as u64
+}
+
+/// 年月日から1970-01-01からの日数を求める
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// 1970-01-01からの日数から年月日を求める
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
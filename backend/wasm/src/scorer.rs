@@ -0,0 +1,315 @@
+//! 差し替え可能なランキングモデル
+//!
+//! 同じ`ViewEvent`・`WindowMetrics`の入力から、異なる定義のトレンドスコアを計算して比べる（A/Bテスト）ため、
+//! スコア計算を`Scorer`トレイトにまとめる。組み込みのモデルは次の4つ。
+//!
+//! - `Trend`: `TrendCalculator::calculate_trend_score`と同じ計算（従来の方法）
+//! - `HackerNews`: (p - 1) / (t + 2)^g（pはポイント、tは公開からの時間数）
+//! - `RedditHot`: log10(p) + (公開時刻 - 基準時刻) / 45000秒
+//! - `ExponentialDecay`: 重み付きの閲覧・行動を経過時間で指数減衰させた和
+//!
+//! ポイントは期間内の閲覧・行動を`HotScoringConfig::point_weights`で重み付けした和。
+//! 時間窓は閲覧数のみを持つため、閲覧として窓の中央の時刻に数える。
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::batch::BatchError;
+use crate::clock::TimeSkewPolicy;
+use crate::config::{HotScoringConfig, ScoringConfig};
+use crate::engine::PeriodRanking;
+use crate::error::{Result, TrendError};
+use crate::period::Period;
+use crate::ranking::{rank_top_n, RankingKey};
+use crate::trend_calculator::{TrendCalculator, ViewEvent, WindowMetrics};
+
+const HOUR_MS: u64 = 60 * 60 * 1000;
+
+/// ランキングモデルに渡す1投稿分の入力
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ScorerPost {
+    pub post_id: u32,
+    #[serde(default)]
+    pub events: Vec<ViewEvent>,
+    #[serde(default)]
+    pub windows: Vec<WindowMetrics>,
+    #[serde(default)]
+    pub published_at: Option<u64>, // 公開時刻（省略時は最初のアクティビティの時刻）
+}
+
+impl ScorerPost {
+    /// 最初のアクティビティの時刻
+    fn first_activity(&self) -> Option<u64> {
+        let events = self.events.iter().map(|e| e.timestamp);
+        let windows = self.windows.iter().map(|w| w.start_time);
+        events.chain(windows).min()
+    }
+
+    /// 最後のアクティビティの時刻
+    fn last_activity(&self) -> Option<u64> {
+        let events = self.events.iter().map(|e| e.timestamp);
+        let windows = self.windows.iter().map(|w| w.end_time);
+        events.chain(windows).max()
+    }
+
+    /// 公開時刻（なければ最初のアクティビティ、それもなければ`now`）
+    fn published_at_or(&self, now: u64) -> u64 {
+        self.published_at.or_else(|| self.first_activity()).unwrap_or(now)
+    }
+
+    /// 期間内の閲覧・行動の時刻とポイント
+    fn weighted_activity<'a>(
+        &'a self,
+        config: &'a HotScoringConfig,
+        period: Period,
+        now: u64,
+    ) -> impl Iterator<Item = (u64, f64)> + 'a {
+        let period_start = now.saturating_sub(period.duration_ms());
+        let weights = &config.point_weights;
+        let events = self
+            .events
+            .iter()
+            .filter(move |e| e.timestamp >= period_start && e.timestamp <= now)
+            .map(move |e| {
+                let weight = match e.event_type.as_deref() {
+                    Some("like") => weights.like,
+                    Some("comment") => weights.comment,
+                    Some("bookmark") => weights.bookmark,
+                    _ => weights.view,
+                };
+                (e.timestamp, weight)
+            });
+        let windows = self
+            .windows
+            .iter()
+            .filter(move |w| w.end_time >= period_start && w.start_time <= now)
+            .map(move |w| {
+                let middle = w.start_time + w.end_time.saturating_sub(w.start_time) / 2;
+                (middle.min(now), w.metrics.total_views as f64 * weights.view)
+            });
+        events.chain(windows)
+    }
+
+    /// 期間内のポイントの合計
+    fn points(&self, config: &HotScoringConfig, period: Period, now: u64) -> f64 {
+        self.weighted_activity(config, period, now).map(|(_, points)| points).sum()
+    }
+}
+
+/// ランキングモデル
+pub trait Scorer {
+    /// モデルの名前
+    fn name(&self) -> &'static str;
+
+    /// 指定時刻（UNIXミリ秒）を現在時刻として1投稿のスコアを計算
+    fn score(&self, post: &ScorerPost, period: Period, now: u64) -> Result<f64>;
+}
+
+/// 従来の計算（`TrendCalculator::calculate_trend_score`）
+pub struct TrendScorer {
+    config: Arc<ScoringConfig>,
+    skew_policy: TimeSkewPolicy,
+}
+
+impl TrendScorer {
+    /// 設定を共有して作成
+    pub fn new(config: Arc<ScoringConfig>) -> Self {
+        TrendScorer {
+            config,
+            skew_policy: TimeSkewPolicy::default(),
+        }
+    }
+
+    /// 未来のタイムスタンプの扱いを差し替える
+    pub fn with_skew_policy(mut self, policy: TimeSkewPolicy) -> Self {
+        self.skew_policy = policy;
+        self
+    }
+}
+
+impl Scorer for TrendScorer {
+    fn name(&self) -> &'static str {
+        "trend"
+    }
+
+    fn score(&self, post: &ScorerPost, period: Period, now: u64) -> Result<f64> {
+        let calculator = TrendCalculator::new(post.post_id, period)
+            .with_skew_policy(self.skew_policy)
+            .with_config(Arc::clone(&self.config));
+        Ok(calculator.score_events_at(&post.events, &post.windows, now)?.score)
+    }
+}
+
+/// Hacker Newsのランキング: (p - 1) / (t + 2)^g
+pub struct HackerNewsScorer {
+    config: Arc<ScoringConfig>,
+}
+
+impl HackerNewsScorer {
+    /// 設定を共有して作成
+    pub fn new(config: Arc<ScoringConfig>) -> Self {
+        HackerNewsScorer { config }
+    }
+}
+
+impl Scorer for HackerNewsScorer {
+    fn name(&self) -> &'static str {
+        "hacker_news"
+    }
+
+    fn score(&self, post: &ScorerPost, period: Period, now: u64) -> Result<f64> {
+        let config = &self.config.hot;
+        let points = post.points(config, period, now);
+        let age_hours = now.saturating_sub(post.published_at_or(now)) as f64 / HOUR_MS as f64;
+        // 投稿者自身の1票を除く
+        Ok((points - 1.0).max(0.0) / (age_hours + 2.0).powf(config.gravity))
+    }
+}
+
+/// Redditのhot: sign(p) × log10(max(|p|, 1)) + (公開時刻 - 基準時刻) / 45000秒
+///
+/// 閲覧・行動は減点しないため、符号は常に正。
+pub struct RedditHotScorer {
+    config: Arc<ScoringConfig>,
+}
+
+impl RedditHotScorer {
+    /// 設定を共有して作成
+    pub fn new(config: Arc<ScoringConfig>) -> Self {
+        RedditHotScorer { config }
+    }
+}
+
+impl Scorer for RedditHotScorer {
+    fn name(&self) -> &'static str {
+        "reddit_hot"
+    }
+
+    fn score(&self, post: &ScorerPost, period: Period, now: u64) -> Result<f64> {
+        let config = &self.config.hot;
+        let order = post.points(config, period, now).max(1.0).log10();
+        let seconds = (post.published_at_or(now) / 1000) as f64 - config.reddit_epoch_secs as f64;
+        let hot = order + seconds / config.reddit_time_divisor;
+        Ok((hot * 1e7).round() / 1e7)
+    }
+}
+
+/// 重み付きの閲覧・行動を経過時間で指数減衰させた和
+pub struct ExponentialDecayScorer {
+    config: Arc<ScoringConfig>,
+}
+
+impl ExponentialDecayScorer {
+    /// 設定を共有して作成
+    pub fn new(config: Arc<ScoringConfig>) -> Self {
+        ExponentialDecayScorer { config }
+    }
+}
+
+impl Scorer for ExponentialDecayScorer {
+    fn name(&self) -> &'static str {
+        "exponential_decay"
+    }
+
+    fn score(&self, post: &ScorerPost, period: Period, now: u64) -> Result<f64> {
+        let config = &self.config.hot;
+        let half_life_ms = config.half_life_hours.get(period) * HOUR_MS as f64;
+        let score: f64 = post
+            .weighted_activity(config, period, now)
+            .map(|(timestamp, points)| points * 0.5f64.powf(now.saturating_sub(timestamp) as f64 / half_life_ms))
+            .sum();
+        Ok((score * 100.0).round() / 100.0)
+    }
+}
+
+/// 組み込みのランキングモデルの種類
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoringModel {
+    #[default]
+    Trend,
+    HackerNews,
+    RedditHot,
+    ExponentialDecay,
+}
+
+impl ScoringModel {
+    /// 全ての組み込みモデル
+    pub const ALL: [ScoringModel; 4] = [
+        ScoringModel::Trend,
+        ScoringModel::HackerNews,
+        ScoringModel::RedditHot,
+        ScoringModel::ExponentialDecay,
+    ];
+
+    /// 設定を共有するモデルを作成
+    pub fn scorer(self, config: Arc<ScoringConfig>) -> Box<dyn Scorer> {
+        match self {
+            ScoringModel::Trend => Box::new(TrendScorer::new(config)),
+            ScoringModel::HackerNews => Box::new(HackerNewsScorer::new(config)),
+            ScoringModel::RedditHot => Box::new(RedditHotScorer::new(config)),
+            ScoringModel::ExponentialDecay => Box::new(ExponentialDecayScorer::new(config)),
+        }
+    }
+}
+
+impl fmt::Display for ScoringModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScoringModel::Trend => write!(f, "trend"),
+            ScoringModel::HackerNews => write!(f, "hacker_news"),
+            ScoringModel::RedditHot => write!(f, "reddit_hot"),
+            ScoringModel::ExponentialDecay => write!(f, "exponential_decay"),
+        }
+    }
+}
+
+impl FromStr for ScoringModel {
+    type Err = TrendError;
+
+    fn from_str(s: &str) -> Result<ScoringModel> {
+        match s.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+            "trend" => Ok(ScoringModel::Trend),
+            "hacker_news" | "hn" => Ok(ScoringModel::HackerNews),
+            "reddit_hot" | "reddit" => Ok(ScoringModel::RedditHot),
+            "exponential_decay" | "decay" => Ok(ScoringModel::ExponentialDecay),
+            _ => Err(TrendError::parse("ランキングモデル", format!("未知のモデルです: {}", s))),
+        }
+    }
+}
+
+/// 指定時刻（UNIXミリ秒）を現在時刻として、モデルのスコアで投稿をランキングする
+///
+/// 個別の投稿の計算エラーはランキング全体を止めず`errors`に記録する。
+pub fn rank_with_scorer(
+    scorer: &dyn Scorer,
+    posts: &[ScorerPost],
+    period: Period,
+    top_n: usize,
+    now: u64,
+) -> PeriodRanking {
+    let mut keys = Vec::with_capacity(posts.len());
+    let mut errors = Vec::new();
+    for post in posts {
+        match scorer.score(post, period, now) {
+            Ok(score) => keys.push(RankingKey {
+                post_id: post.post_id,
+                score,
+                unique_users: post.events.iter().map(|e| e.user_id).collect::<HashSet<_>>().len() as u32,
+                last_activity: post.last_activity().unwrap_or(0),
+            }),
+            Err(error) => errors.push(BatchError {
+                post_id: post.post_id,
+                error,
+            }),
+        }
+    }
+    PeriodRanking {
+        period,
+        ranking: rank_top_n(keys, top_n),
+        errors,
+    }
+}
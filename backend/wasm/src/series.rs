@@ -0,0 +1,185 @@
+//! シリーズの急上昇スコア計算
+//!
+//! seriesTrendingCalculator.js の`calculateSeriesTrendingScore`を移植したもの。
+//! エピソード平均スコア×3とフォロワー増加数×7をベースに、
+//! 時間減衰・フォロワー増加の勢い・エピソード数ボーナスを掛け合わせる。
+
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::clock::{Clock, SystemClock, TimeSkewPolicy};
+use crate::config::ScoringConfig;
+use crate::error::Result;
+use crate::logging::log_calculation;
+use crate::period::Period;
+
+/// フォロワー数の履歴（SeriesFollowerHistory）の1件
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FollowerSnapshot {
+    pub timestamp: u64,      // 記録時刻（UNIXミリ秒）
+    pub follower_count: u32, // その時点のフォロワー数
+}
+
+/// シリーズのスコア計算用データ
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SeriesTrendData {
+    pub series_id: u32,
+    pub episode_scores: Vec<f64>,              // 各エピソードの期間スコア
+    pub episode_count: u32,                    // エピソード数
+    pub current_follower_count: u32,           // 現在のフォロワー数
+    #[serde(default)]
+    pub follower_history: Vec<FollowerSnapshot>,
+    #[serde(default)]
+    pub previous_follower_rate: Option<f64>,   // 前回の1時間あたりフォロワー増加数（0以下は下限として扱う）
+    pub updated_at: u64,                       // シリーズの更新時刻（UNIXミリ秒）
+}
+
+/// シリーズのスコア計算結果
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SeriesTrendResult {
+    pub series_id: u32,
+    pub score: f64,
+    pub base_score: f64,
+    pub episode_average_score: f64,
+    pub follower_increase: u32,
+    pub time_decay: f64,
+    pub momentum: f64,
+    pub episode_bonus: f64,
+}
+
+/// シリーズの急上昇スコア計算機
+pub struct SeriesTrendCalculator {
+    period: Period,
+    clock: Box<dyn Clock>,
+    skew_policy: TimeSkewPolicy,
+    config: Arc<ScoringConfig>,
+}
+
+impl SeriesTrendCalculator {
+    /// 新しいシリーズ計算機を作成
+    pub fn new(period: Period) -> Self {
+        SeriesTrendCalculator {
+            period,
+            clock: Box::new(SystemClock),
+            skew_policy: TimeSkewPolicy::default(),
+            config: Arc::new(ScoringConfig::default()),
+        }
+    }
+
+    /// 現在時刻の取得元を差し替える
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    /// 未来のタイムスタンプの扱いを差し替える
+    pub fn with_skew_policy(mut self, policy: TimeSkewPolicy) -> Self {
+        self.skew_policy = policy;
+        self
+    }
+
+    /// スコア計算の設定を差し替える（投稿の計算機と共有可能）
+    pub fn with_config(mut self, config: Arc<ScoringConfig>) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// 設定された時計から現在時刻（UNIXミリ秒）を取得
+    pub fn now_ms(&self) -> u64 {
+        self.clock.now_ms()
+    }
+
+    /// シリーズの急上昇スコアを計算
+    pub fn calculate(&self, data: &SeriesTrendData) -> Result<SeriesTrendResult> {
+        self.calculate_at(data, self.clock.now_ms())
+    }
+
+    /// 指定時刻（UNIXミリ秒）を現在時刻としてシリーズのスコアを計算
+    pub fn calculate_at(&self, data: &SeriesTrendData, now: u64) -> Result<SeriesTrendResult> {
+        let config = &self.config.series;
+
+        // 1. ベーススコア（エピソード平均スコアとフォロワー増加数）
+        let episode_average_score = episode_average_score(&data.episode_scores);
+        let period_start = now.saturating_sub(self.period.duration_ms());
+        let follower_increase = follower_increase(data, period_start);
+        let base_score = episode_average_score * config.episode_weight
+            + follower_increase as f64 * config.follower_weight;
+
+        // 2. 時間減衰（最終活動はシリーズ更新とフォロワー履歴の新しい方）
+        let last_activity = data
+            .follower_history
+            .iter()
+            .map(|h| h.timestamp)
+            .chain(std::iter::once(data.updated_at))
+            .max()
+            .unwrap_or(data.updated_at);
+        let elapsed_ms = self.skew_policy.elapsed_since(last_activity, now)?;
+        let hours_elapsed = elapsed_ms as f64 / (1000.0 * 60.0 * 60.0);
+        let decay_rate = config.decay_rates.get(self.period);
+        let time_decay = (-decay_rate * hours_elapsed).exp();
+
+        // 3. フォロワー増加の勢い
+        // JSは`followerRate || 0.01`のため負の前回増加率をそのまま使い、加速度が負になって
+        // log10の引数が負（スコアがNaN）になりうる。ここでは0以下の値をすべて下限に置き換える
+        let current_rate = follower_increase as f64 / self.period.hours();
+        let previous_rate = data
+            .previous_follower_rate
+            .filter(|rate| *rate > 0.0)
+            .unwrap_or(config.min_previous_rate);
+        let acceleration = current_rate / previous_rate;
+        let momentum_weight = config.momentum_weights.get(self.period);
+        let momentum = ((acceleration + 1.0).log10() * momentum_weight).min(config.momentum_cap);
+
+        // 4. エピソード数に応じたボーナス
+        let episode_bonus = 1.0 + (data.episode_count as f64 + 1.0).log10() * config.episode_bonus_scale;
+
+        // 5. 最終スコア（小数点以下2桁に丸める）
+        let final_score = base_score * time_decay * (1.0 + momentum) * episode_bonus;
+        let score = (final_score * 100.0).round() / 100.0;
+
+        let result = SeriesTrendResult {
+            series_id: data.series_id,
+            score,
+            base_score,
+            episode_average_score,
+            follower_increase,
+            time_decay,
+            momentum,
+            episode_bonus,
+        };
+
+        log_calculation(data.series_id, "series_score",
+            "シリーズの急上昇スコアを計算",
+            || &result
+        );
+
+        Ok(result)
+    }
+}
+
+/// スコアが正のエピソードだけの平均スコア
+fn episode_average_score(scores: &[f64]) -> f64 {
+    let (total, count) = scores
+        .iter()
+        .filter(|score| **score > 0.0)
+        .fold((0.0, 0), |(total, count), score| (total + score, count + 1));
+
+    if count > 0 {
+        total / count as f64
+    } else {
+        0.0
+    }
+}
+
+/// 期間開始時点からのフォロワー増加数（減少した場合は0）
+fn follower_increase(data: &SeriesTrendData, period_start: u64) -> u32 {
+    let start_count = data
+        .follower_history
+        .iter()
+        .filter(|h| h.timestamp <= period_start)
+        .max_by_key(|h| h.timestamp)
+        .map(|h| h.follower_count)
+        .unwrap_or(0);
+
+    data.current_follower_count.saturating_sub(start_count)
+}
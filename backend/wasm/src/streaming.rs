@@ -0,0 +1,246 @@
+//! イベントを逐次取り込むトレンド計算機
+//!
+//! `TrendCalculator`は計算のたびに全イベント・全時間窓を受け取るが、
+//! `StreamingTrendCalculator`はイベントを時間ごとの集計（件数・種類別の件数・
+//! ユニークユーザー）に畳み込んで保持する。スコアは保持している時間数に比例する
+//! 計算量で求められ、過去のイベントを再解析しない。
+//!
+//! - 期間の境界をまたぐ時間のイベントは、その時間全体を期間内として扱う
+//! - ユニークユーザーは時間ごとに正確な集合で持ち、`EXACT_USERS_LIMIT`人を超えた
+//!   時間はHyperLogLogに切り替える（1時間あたりのメモリを一定に抑える）
+//! - 不正なイベントの除外（`set_abuse_filter`）はイベントの履歴が必要なため使えない。
+//!   疑わしさによる割り引き（`set_suspicion`）は使える
+
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+
+use crate::clock::{Clock, TimeSkewPolicy};
+use crate::config::ScoringConfig;
+use crate::error::Result;
+use crate::hyperloglog::HyperLogLog;
+use crate::period::Period;
+use crate::trend_calculator::{EventSummary, EventTally, TrendCalculator, TrendStats, ViewEvent, WindowMetrics};
+
+const HOUR_MS: u64 = 60 * 60 * 1000;
+/// 正確な集合で保持するユニークユーザー数の上限（HyperLogLogとほぼ同じメモリ量）
+const EXACT_USERS_LIMIT: usize = 2048;
+
+/// 1時間分のユニークユーザー
+#[derive(Debug, Clone)]
+enum HourUsers {
+    Exact(HashSet<u32>),
+    Sketch(HyperLogLog),
+}
+
+impl HourUsers {
+    fn insert(&mut self, user_id: u32) {
+        match self {
+            HourUsers::Exact(users) => {
+                users.insert(user_id);
+                if users.len() > EXACT_USERS_LIMIT {
+                    let mut sketch = HyperLogLog::new();
+                    users.iter().for_each(|user| {
+                        sketch.add_user(*user);
+                    });
+                    *self = HourUsers::Sketch(sketch);
+                }
+            }
+            HourUsers::Sketch(sketch) => {
+                sketch.add_user(user_id);
+            }
+        }
+    }
+}
+
+/// 1時間分のイベントの集計
+#[derive(Debug, Clone)]
+struct HourBucket {
+    tally: EventTally,
+    engagement_sum: f64,
+    users: HourUsers,
+}
+
+/// 1投稿分の時間窓と時間ごとのイベントの集計（期間によらず共通）
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PostActivity {
+    windows: BTreeMap<u64, WindowMetrics>, // 開始時刻ごとの集約済み時間窓
+    hours: BTreeMap<u64, HourBucket>,      // 時間（UNIXミリ秒 / 1時間）ごとのイベントの集計
+    evicted_before: u64,                   // これより前のデータは破棄済み
+}
+
+impl PostActivity {
+    pub(crate) fn event_count(&self) -> u32 {
+        self.hours.values().fold(0u32, |sum, h| sum.saturating_add(h.tally.events))
+    }
+
+    pub(crate) fn hour_count(&self) -> usize {
+        self.hours.len()
+    }
+
+    pub(crate) fn window_count(&self) -> usize {
+        self.windows.len()
+    }
+
+    pub(crate) fn push_event(&mut self, event: &ViewEvent) -> bool {
+        if event.timestamp < self.evicted_before {
+            return false;
+        }
+        let bucket = self.hours.entry(event.timestamp / HOUR_MS).or_insert_with(|| HourBucket {
+            tally: EventTally::default(),
+            engagement_sum: 0.0,
+            users: HourUsers::Exact(HashSet::new()),
+        });
+        bucket.tally.add(event);
+        bucket.engagement_sum += event.engagement_score;
+        bucket.users.insert(event.user_id);
+        true
+    }
+
+    pub(crate) fn push_window(&mut self, window: WindowMetrics) -> bool {
+        if window.end_time < self.evicted_before {
+            return false;
+        }
+        self.windows.insert(window.start_time, window);
+        true
+    }
+
+    pub(crate) fn evict_before(&mut self, timestamp: u64) {
+        self.evicted_before = self.evicted_before.max(timestamp);
+        self.windows.retain(|_, w| w.end_time >= timestamp);
+        self.hours = self.hours.split_off(&(timestamp / HOUR_MS));
+    }
+
+    /// 最後のアクティビティの時刻
+    pub(crate) fn last_activity(&self) -> Option<u64> {
+        self.hours.values().filter_map(|bucket| bucket.tally.last_timestamp).max()
+    }
+
+    /// 計算機の期間・設定でスコアを計算
+    pub(crate) fn score(&self, calculator: &TrendCalculator, suspicion: f64, now: u64) -> Result<TrendStats> {
+        let period_start = now.saturating_sub(calculator.period().duration_ms());
+        let in_period = self.hours.range(period_start / HOUR_MS..=now / HOUR_MS);
+
+        // 期間内の時間の集計を結合（全ての時間が正確な集合ならユニークユーザー数も正確に求める）
+        let mut recent = EventSummary::default();
+        let mut exact_users: HashSet<u32> = HashSet::new();
+        let mut all_exact = true;
+        for (hour, bucket) in in_period {
+            recent.tally.merge(&bucket.tally);
+            recent.engagement_sum += bucket.engagement_sum;
+            recent.hourly_counts.insert(*hour, bucket.tally.events);
+            match &bucket.users {
+                HourUsers::Exact(users) => exact_users.extend(users),
+                HourUsers::Sketch(sketch) => {
+                    recent.sketch.merge(sketch);
+                    all_exact = false;
+                }
+            }
+        }
+        for user in &exact_users {
+            recent.sketch.add_user(*user);
+        }
+        recent.unique_users = if all_exact {
+            exact_users.len() as u32
+        } else {
+            recent.sketch.count().min(u32::MAX as u64) as u32
+        };
+
+        // イベントタイプの分布と最後のアクティビティは保持している全イベントから求める
+        let mut tally = EventTally::default();
+        self.hours.values().for_each(|bucket| tally.merge(&bucket.tally));
+
+        let windows: Vec<&WindowMetrics> = self.windows.values().collect();
+        calculator.score_summaries(&windows, &recent, &tally, suspicion, now)
+    }
+}
+
+/// イベント・時間窓を逐次取り込むトレンド計算機
+pub struct StreamingTrendCalculator {
+    calculator: TrendCalculator, // 設定・時計・スコア計算
+    activity: PostActivity,
+}
+
+impl StreamingTrendCalculator {
+    /// 新しい計算機を作成
+    pub fn new(post_id: u32, period: Period) -> Self {
+        StreamingTrendCalculator {
+            calculator: TrendCalculator::new(post_id, period),
+            activity: PostActivity::default(),
+        }
+    }
+
+    /// 現在時刻の取得元を差し替える
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.calculator.set_clock(clock);
+        self
+    }
+
+    /// 現在時刻の取得元を設定
+    pub fn set_clock(&mut self, clock: impl Clock + 'static) {
+        self.calculator.set_clock(clock);
+    }
+
+    /// 未来のタイムスタンプの扱いを差し替える
+    pub fn with_skew_policy(mut self, policy: TimeSkewPolicy) -> Self {
+        self.calculator.set_skew_policy(policy);
+        self
+    }
+
+    /// スコア計算の設定を差し替える
+    pub fn with_config(mut self, config: Arc<ScoringConfig>) -> Self {
+        self.calculator.set_config(config);
+        self
+    }
+
+    /// 疑わしさ（0〜1）を設定し、スコアを割り引く
+    pub fn set_suspicion(&mut self, suspicion: f64) {
+        self.calculator.set_suspicion(suspicion);
+    }
+
+    /// 投稿IDを取得
+    pub fn post_id(&self) -> u32 {
+        self.calculator.post_id()
+    }
+
+    /// 保持しているイベント数
+    pub fn event_count(&self) -> u32 {
+        self.activity.event_count()
+    }
+
+    /// 保持している時間数
+    pub fn hour_count(&self) -> usize {
+        self.activity.hour_count()
+    }
+
+    /// 保持している時間窓の数
+    pub fn window_count(&self) -> usize {
+        self.activity.window_count()
+    }
+
+    /// イベントを取り込む（破棄済みの時刻より前のイベントは無視してfalseを返す）
+    pub fn push_event(&mut self, event: &ViewEvent) -> bool {
+        self.activity.push_event(event)
+    }
+
+    /// 集約済みの時間窓を取り込む（同じ開始時刻の窓は置き換える）
+    pub fn push_window(&mut self, window: WindowMetrics) -> bool {
+        self.activity.push_window(window)
+    }
+
+    /// 指定時刻より前のデータを破棄する
+    ///
+    /// 時間窓は終了時刻が指定時刻より前のものを、イベントは時間全体が指定時刻より前のものを破棄する。
+    pub fn evict_before(&mut self, timestamp: u64) {
+        self.activity.evict_before(timestamp);
+    }
+
+    /// 現在時刻でスコアを計算
+    pub fn calculate_trend_score(&self) -> Result<TrendStats> {
+        self.calculate_trend_score_at(self.calculator.now_ms())
+    }
+
+    /// 指定時刻（UNIXミリ秒）を現在時刻としてスコアを計算
+    pub fn calculate_trend_score_at(&self, now: u64) -> Result<TrendStats> {
+        self.activity.score(&self.calculator, self.calculator.suspicion(), now)
+    }
+}
@@ -0,0 +1,231 @@
+//! 総合（全期間）ランキングのスコア計算
+//!
+//! totalPostRankingCalculator.js の`calculateTotalScore`と
+//! totalSeriesRankingCalculator.js の`calculateTotalSeriesScore`を移植したもの。
+
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::config::ScoringConfig;
+use crate::logging::log_calculation;
+use crate::ranking::{rank_top_n, RankedPost, RankingKey};
+
+/// 投稿の総合スコア計算用データ
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TotalPostInput {
+    pub post_id: u32,
+    pub comment_count: u32,  // コメント数
+    pub bookmark_count: u32, // 本棚追加数
+    pub like_count: u32,     // いいね数
+    pub unique_viewers: u32, // ユニーク閲覧者数
+}
+
+/// 投稿の総合スコアと内訳
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TotalPostScore {
+    pub post_id: u32,
+    pub score: f64,
+    pub comment_score: f64,
+    pub bookmark_score: f64,
+    pub like_score: f64,
+    pub view_score: f64,
+    pub unique_users_count: u32,
+    pub unique_user_factor: f64,
+}
+
+/// シリーズの総合スコア計算用データ
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TotalSeriesInput {
+    pub series_id: u32,
+    pub follower_count: u32,     // フォロワー数
+    pub episode_count: u32,      // エピソード数
+    pub average_post_score: f64, // エピソードの平均総合スコア
+    #[serde(default)]
+    pub is_completed: bool,      // 完結済みか
+}
+
+/// シリーズの総合スコアと内訳
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TotalSeriesScore {
+    pub series_id: u32,
+    pub score: f64,
+    pub follower_score: f64,
+    pub episode_score: f64,
+    pub completed_bonus: f64,
+}
+
+/// シリーズのランキング上の1件
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RankedSeries {
+    pub rank: u32,
+    pub series_id: u32,
+    pub score: f64,
+}
+
+/// 投稿の総合ランキング
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct TotalPostRanking {
+    pub scores: Vec<TotalPostScore>, // 入力順のスコア
+    pub ranking: Vec<RankedPost>,    // スコア降順の上位N件
+}
+
+/// シリーズの総合ランキング
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct TotalSeriesRanking {
+    pub scores: Vec<TotalSeriesScore>, // 入力順のスコア
+    pub ranking: Vec<RankedSeries>,    // スコア降順の上位N件
+}
+
+/// 総合ランキングのスコア計算機
+#[derive(Default)]
+pub struct TotalRankCalculator {
+    config: Arc<ScoringConfig>,
+}
+
+impl TotalRankCalculator {
+    /// 既定の設定で計算機を作成
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// スコア計算の設定を差し替える
+    pub fn with_config(mut self, config: Arc<ScoringConfig>) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// 投稿の総合スコアを計算
+    pub fn score_post(&self, input: &TotalPostInput) -> TotalPostScore {
+        let config = &self.config.total;
+
+        // 各メトリクスのスコア
+        let comment_score = input.comment_count as f64 * config.post_weights.comment;
+        let bookmark_score = input.bookmark_count as f64 * config.post_weights.bookmark;
+        let like_score = input.like_count as f64 * config.post_weights.like;
+        let view_score = input.unique_viewers as f64 * config.post_weights.view;
+        let base_score = comment_score + bookmark_score + like_score + view_score;
+
+        // ユニークユーザー係数（既定では200人で上限の2.0）
+        let unique_user_factor = if input.unique_viewers > 0 {
+            (1.0 + input.unique_viewers as f64 * config.unique_user_step).min(config.unique_user_factor_cap)
+        } else {
+            1.0
+        };
+
+        let score = (base_score * unique_user_factor * 100.0).round() / 100.0;
+
+        let result = TotalPostScore {
+            post_id: input.post_id,
+            score,
+            comment_score,
+            bookmark_score,
+            like_score,
+            view_score,
+            unique_users_count: input.unique_viewers,
+            unique_user_factor,
+        };
+
+        log_calculation(input.post_id, "total_score",
+            "総合スコアを計算",
+            || &result
+        );
+
+        result
+    }
+
+    /// シリーズの総合スコアを計算
+    pub fn score_series(&self, input: &TotalSeriesInput) -> TotalSeriesScore {
+        let config = &self.config.total;
+
+        // フォロワースコア（対数スケール: 10人で約10点、100人で約20点）
+        let follower_score = if input.follower_count > 0 {
+            (input.follower_count as f64 + 1.0).log10() * config.follower_log_scale
+        } else {
+            0.0
+        };
+
+        // エピソードスコア（平均スコアがない場合はエピソード数に応じた最小スコア）
+        let episode_score = if input.average_post_score > 0.0 {
+            input.average_post_score
+        } else {
+            input.episode_count as f64 * config.default_episode_score
+        };
+
+        let completed_bonus = if input.is_completed { config.completed_bonus } else { 1.0 };
+
+        // フォロワーがいない場合はエピソードスコアのみで評価
+        let raw_score = if follower_score > 0.0 {
+            follower_score * config.follower_weight + episode_score * config.episode_weight
+        } else {
+            episode_score
+        };
+
+        // 最低限ランキングに表示されるよう下限を設ける
+        let rounded = (raw_score * completed_bonus * 100.0).round() / 100.0;
+        let score = if rounded > 0.0 { rounded } else { config.min_series_score };
+
+        let result = TotalSeriesScore {
+            series_id: input.series_id,
+            score,
+            follower_score,
+            episode_score,
+            completed_bonus,
+        };
+
+        log_calculation(input.series_id, "total_series_score",
+            "シリーズの総合スコアを計算",
+            || &result
+        );
+
+        result
+    }
+
+    /// 投稿の総合スコアを一括計算し上位N件のランキングを作成
+    ///
+    /// 同点の場合はユニーク閲覧者数の多い方、次に投稿IDの小さい方を上位とする。
+    pub fn rank_posts(&self, inputs: &[TotalPostInput], top_n: usize) -> TotalPostRanking {
+        let scores: Vec<TotalPostScore> = inputs.iter().map(|input| self.score_post(input)).collect();
+        let keys = scores
+            .iter()
+            .map(|s| RankingKey {
+                post_id: s.post_id,
+                score: s.score,
+                unique_users: s.unique_users_count,
+                last_activity: 0,
+            })
+            .collect();
+
+        TotalPostRanking {
+            ranking: rank_top_n(keys, top_n),
+            scores,
+        }
+    }
+
+    /// シリーズの総合スコアを一括計算し上位N件のランキングを作成
+    ///
+    /// 同点の場合はフォロワー数の多い方、次にシリーズIDの小さい方を上位とする。
+    pub fn rank_series(&self, inputs: &[TotalSeriesInput], top_n: usize) -> TotalSeriesRanking {
+        let scores: Vec<TotalSeriesScore> = inputs.iter().map(|input| self.score_series(input)).collect();
+        let keys = scores
+            .iter()
+            .zip(inputs)
+            .map(|(s, input)| RankingKey {
+                post_id: s.series_id,
+                score: s.score,
+                unique_users: input.follower_count,
+                last_activity: 0,
+            })
+            .collect();
+
+        let ranking = rank_top_n(keys, top_n)
+            .into_iter()
+            .map(|r| RankedSeries {
+                rank: r.rank,
+                series_id: r.post_id,
+                score: r.score,
+            })
+            .collect();
+
+        TotalSeriesRanking { scores, ranking }
+    }
+}
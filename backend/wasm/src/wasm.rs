@@ -0,0 +1,776 @@
+//! JavaScript向けのwasm-bindgenバインディング
+//!
+//! JSON文字列で受け取った入力を解析し、純Rustの計算エンジンへ委譲する。
+//! エラーは`code`と`details`を持つJSの`Error`として投げる。
+
+use serde::Serialize;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+use wasm_bindgen::prelude::*;
+
+use crate::abuse::AbuseDetector;
+use crate::batch::{BatchPostInput, BatchScorer};
+use crate::clock::{Clock, FixedClock, SystemClock, TimeSkewPolicy};
+use crate::config::ScoringConfig;
+use crate::dedup::{DedupConfig, ViewDeduplicator};
+use crate::engagement::{EngagementCounts, EngagementRates};
+use crate::engine::TrendEngine;
+use crate::error::TrendError;
+use crate::explain::ScoreExplanation;
+use crate::growth::estimate_growth;
+use crate::logging::{
+    log_event, reset_logging, set_log_level, set_log_sink, trace_all_posts, trace_post, untrace_post,
+    ConsoleSink, LogLevel, LogRecord, LogSink, NoopSink, RingBufferSink,
+};
+use crate::packing::{BinaryViewPacker, PackFormat, ViewRecord};
+use crate::rollup::{diff_windows, rollup_all, rollup_events, rollup_windows, RollupLevel, RollupWindow};
+use crate::period::Period;
+use crate::scorer::{rank_with_scorer, ScorerPost, ScoringModel};
+use crate::series::{SeriesTrendCalculator, SeriesTrendData};
+use crate::streaming::StreamingTrendCalculator;
+use crate::total::{TotalPostInput, TotalRankCalculator, TotalSeriesInput};
+use crate::trend_calculator::{
+    DirectCalculationData, RedisHllData, TrendCalculator, ViewEvent, WindowMetrics,
+};
+
+// ログ出力用のJavaScript関数をインポート
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = console, js_name = log)]
+    pub(crate) fn console_log(s: &str);
+}
+
+thread_local! {
+    // 全計算機で共有するスコア設定
+    static SCORING_CONFIG: RefCell<Arc<ScoringConfig>> = RefCell::new(Arc::new(ScoringConfig::default()));
+    // JSから読み出すログのリングバッファ（出力先に設定した場合のみ）
+    static LOG_BUFFER: RefCell<Option<Rc<RingBufferSink>>> = const { RefCell::new(None) };
+}
+
+/// JSのコールバック関数`(post_id, action, message, data_json, level)`への出力先
+struct JsCallbackSink {
+    callback: js_sys::Function,
+}
+
+impl LogSink for JsCallbackSink {
+    fn write(&self, record: &LogRecord) {
+        let args = js_sys::Array::of5(
+            &JsValue::from(record.post_id),
+            &JsValue::from_str(&record.action),
+            &JsValue::from_str(&record.message),
+            &JsValue::from_str(&record.data.to_string()),
+            &JsValue::from_str(&record.level.to_string()),
+        );
+        // コールバック内の例外で計算を止めない
+        let _ = self.callback.apply(&JsValue::NULL, &args);
+    }
+}
+
+/// 現在の共有スコア設定を取得
+fn shared_config() -> Arc<ScoringConfig> {
+    SCORING_CONFIG.with(|config| Arc::clone(&config.borrow()))
+}
+
+/// JSから渡された期間を解析する
+///
+/// 従来の期間タイプの数値 (0〜3) と、`'daily'`/`'day'`/`'last 6 hours'`などの文字列を受け付ける。
+fn parse_period(value: &JsValue) -> Result<Period, JsError> {
+    let period = if let Some(number) = value.as_f64() {
+        if number.fract() != 0.0 || !(0.0..=255.0).contains(&number) {
+            Err(TrendError::InvalidPeriod { value: number.to_string() })
+        } else {
+            Period::from_type(number as u8)
+        }
+    } else if let Some(text) = value.as_string() {
+        text.parse()
+    } else {
+        Err(TrendError::InvalidPeriod { value: format!("{:?}", value) })
+    };
+    period.map_err(to_js_error)
+}
+
+/// 入力JSONの先頭100バイトをプレビュー用に切り出す
+fn input_preview(json: &str) -> String {
+    if json.len() > 100 {
+        let mut end = 100;
+        while !json.is_char_boundary(end) {
+            end -= 1;
+        }
+        format!("{}...", &json[..end])
+    } else {
+        json.to_string()
+    }
+}
+
+/// TrendErrorを構造化されたJSのErrorに変換
+///
+/// `name`は"TrendError"、`code`はエラーコード、`details`はエラー内容のオブジェクト。
+fn to_js_error(err: TrendError) -> JsError {
+    let js_error = JsError::new(&err.to_string());
+    let value: JsValue = js_error.clone().into();
+    let details = serde_wasm_bindgen::to_value(&err).unwrap_or(JsValue::NULL);
+    let _ = js_sys::Reflect::set(&value, &"name".into(), &"TrendError".into());
+    let _ = js_sys::Reflect::set(&value, &"code".into(), &err.code().into());
+    let _ = js_sys::Reflect::set(&value, &"details".into(), &details);
+    js_error
+}
+
+/// 計算結果をJavaScriptの値に変換
+fn to_js<T: Serialize>(value: &T) -> Result<JsValue, JsError> {
+    Ok(serde_wasm_bindgen::to_value(value)?)
+}
+
+/// JSON文字列を解析し、失敗した場合はログを出力してエラーを返す
+fn parse_json<T: serde::de::DeserializeOwned>(post_id: u32, target: &str, json: &str) -> Result<T, JsError> {
+    serde_json::from_str::<T>(json).map_err(|e| {
+        // JSON解析エラーをログ
+        log_event(LogLevel::Warn, post_id, "error", 
+            &format!("{}のJSONを解析できませんでした", target), 
+            || serde_json::json!({
+                "error": e.to_string(),
+                "input_preview": input_preview(json)
+            })
+        );
+        to_js_error(TrendError::parse(target, e))
+    })
+}
+
+/// JavaScriptから利用するトレンド計算機
+#[wasm_bindgen(js_name = TrendCalculator)]
+pub struct WasmTrendCalculator {
+    inner: TrendCalculator,
+}
+
+#[wasm_bindgen(js_class = TrendCalculator)]
+impl WasmTrendCalculator {
+    /// 新しいトレンド計算機を作成
+    ///
+    /// `period`は期間タイプの数値 (0〜3) または`'daily'`・`'last 6 hours'`などの文字列。
+    #[wasm_bindgen(constructor)]
+    pub fn new(post_id: u32, period: JsValue) -> Result<WasmTrendCalculator, JsError> {
+        let inner = TrendCalculator::new(post_id, parse_period(&period)?)
+            .with_config(shared_config());
+        Ok(WasmTrendCalculator { inner })
+    }
+
+    /// 現在時刻を固定する（バックフィル用、UNIXミリ秒）
+    pub fn set_fixed_now(&mut self, now_ms: f64) {
+        self.inner.set_clock(FixedClock(now_ms as u64));
+    }
+
+    /// 現在時刻をシステム時刻に戻す
+    pub fn use_system_clock(&mut self) {
+        self.inner.set_clock(SystemClock);
+    }
+
+    /// 未来のタイムスタンプの扱いを設定（"clamp" または "reject"）
+    pub fn set_time_skew_policy(&mut self, policy: &str) -> Result<(), JsError> {
+        let policy: TimeSkewPolicy = policy.parse().map_err(to_js_error)?;
+        self.inner.set_skew_policy(policy);
+        Ok(())
+    }
+
+    /// 不正なイベントを除外してから計算するかを設定
+    pub fn set_abuse_filter(&mut self, enabled: bool) {
+        self.inner.set_abuse_filter(enabled);
+    }
+
+    /// 疑わしさ（0〜1）を設定し、スコアを割り引く
+    pub fn set_suspicion(&mut self, suspicion: f64) {
+        self.inner.set_suspicion(suspicion);
+    }
+
+    /// 集約済み時間窓データを設定
+    ///
+    /// 解析に失敗した場合は以前のデータを保持したままエラーを投げる。
+    pub fn set_aggregated_windows(&mut self, windows_json: &str) -> Result<(), JsError> {
+        let windows: Vec<WindowMetrics> = parse_json(self.inner.post_id(), "時間窓データ", windows_json)?;
+        self.inner.set_aggregated_windows(windows);
+        Ok(())
+    }
+
+    /// 未集約の最近のイベントを設定
+    ///
+    /// 解析に失敗した場合は以前のデータを保持したままエラーを投げる。
+    pub fn set_recent_events(&mut self, events_json: &str) -> Result<(), JsError> {
+        let events: Vec<ViewEvent> = parse_json(self.inner.post_id(), "イベントデータ", events_json)?;
+        self.inner.set_recent_events(events);
+        Ok(())
+    }
+
+    /// メイン計算関数（従来の複雑なロジック）
+    pub fn calculate_trend_score(&self) -> Result<JsValue, JsError> {
+        to_js(&self.inner.calculate_trend_score().map_err(to_js_error)?)
+    }
+
+    /// 指定時刻（UNIXミリ秒）を現在時刻としてメイン計算を行う
+    pub fn calculate_trend_score_at(&self, now_ms: f64) -> Result<JsValue, JsError> {
+        to_js(&self.inner.calculate_trend_score_at(now_ms as u64).map_err(to_js_error)?)
+    }
+
+    /// 新しい仕様での直接計算（簡素化版）
+    pub fn calculate_trending_score_direct(&self, calc_data_json: &str) -> Result<JsValue, JsError> {
+        self.calculate_trending_score_direct_at(calc_data_json, self.inner.now_ms() as f64)
+    }
+
+    /// 指定時刻（UNIXミリ秒）を現在時刻として直接計算を行う
+    pub fn calculate_trending_score_direct_at(&self, calc_data_json: &str, now_ms: f64) -> Result<JsValue, JsError> {
+        let calc_data: DirectCalculationData = parse_json(self.inner.post_id(), "計算データ", calc_data_json)?;
+        let result = self
+            .inner
+            .calculate_trending_score_direct_at(&calc_data, now_ms as u64)
+            .map_err(to_js_error)?;
+        to_js(&result)
+    }
+
+    /// Redis HLLデータに基づいて直接計算する
+    pub fn calculate_with_redis_hll_data(&self, redis_data_json: &str) -> Result<JsValue, JsError> {
+        self.calculate_with_redis_hll_data_at(redis_data_json, self.inner.now_ms() as f64)
+    }
+
+    /// 指定時刻（UNIXミリ秒）を現在時刻としてRedis HLLデータから計算する
+    pub fn calculate_with_redis_hll_data_at(&self, redis_data_json: &str, now_ms: f64) -> Result<JsValue, JsError> {
+        let redis_data: RedisHllData = parse_json(self.inner.post_id(), "Redis HLLデータ", redis_data_json)?;
+        let stats = self
+            .inner
+            .calculate_with_redis_hll_data_at(&redis_data, now_ms as u64)
+            .map_err(to_js_error)?;
+        to_js(&stats)
+    }
+}
+
+/// JavaScriptから利用するイベントを逐次取り込むトレンド計算機
+#[wasm_bindgen(js_name = StreamingTrendCalculator)]
+pub struct WasmStreamingTrendCalculator {
+    inner: StreamingTrendCalculator,
+}
+
+#[wasm_bindgen(js_class = StreamingTrendCalculator)]
+impl WasmStreamingTrendCalculator {
+    /// 新しいストリーミング計算機を作成（`period`は`TrendCalculator`と同じ形式）
+    #[wasm_bindgen(constructor)]
+    pub fn new(post_id: u32, period: JsValue) -> Result<WasmStreamingTrendCalculator, JsError> {
+        let inner = StreamingTrendCalculator::new(post_id, parse_period(&period)?).with_config(shared_config());
+        Ok(WasmStreamingTrendCalculator { inner })
+    }
+
+    /// 現在時刻を固定する（バックフィル用、UNIXミリ秒）
+    pub fn set_fixed_now(&mut self, now_ms: f64) {
+        self.inner.set_clock(FixedClock(now_ms as u64));
+    }
+
+    /// 現在時刻をシステム時刻に戻す
+    pub fn use_system_clock(&mut self) {
+        self.inner.set_clock(SystemClock);
+    }
+
+    /// 疑わしさ（0〜1）を設定し、スコアを割り引く
+    pub fn set_suspicion(&mut self, suspicion: f64) {
+        self.inner.set_suspicion(suspicion);
+    }
+
+    /// イベントを1件取り込む（破棄済みの時刻より前ならfalse）
+    pub fn push_event(&mut self, event_json: &str) -> Result<bool, JsError> {
+        let event: ViewEvent = parse_json(self.inner.post_id(), "イベントデータ", event_json)?;
+        Ok(self.inner.push_event(&event))
+    }
+
+    /// イベントの配列を取り込み、取り込んだ件数を返す
+    pub fn push_events(&mut self, events_json: &str) -> Result<u32, JsError> {
+        let events: Vec<ViewEvent> = parse_json(self.inner.post_id(), "イベントデータ", events_json)?;
+        Ok(events.iter().filter(|event| self.inner.push_event(event)).count() as u32)
+    }
+
+    /// 集約済みの時間窓を1件取り込む（同じ開始時刻の窓は置き換える）
+    pub fn push_window(&mut self, window_json: &str) -> Result<bool, JsError> {
+        let window: WindowMetrics = parse_json(self.inner.post_id(), "時間窓データ", window_json)?;
+        Ok(self.inner.push_window(window))
+    }
+
+    /// 指定時刻（UNIXミリ秒）より前のデータを破棄する
+    pub fn evict_before(&mut self, timestamp_ms: f64) {
+        self.inner.evict_before(timestamp_ms as u64);
+    }
+
+    /// 保持しているイベント数
+    pub fn event_count(&self) -> u32 {
+        self.inner.event_count()
+    }
+
+    /// 現在時刻でスコアを計算
+    pub fn calculate_trend_score(&self) -> Result<JsValue, JsError> {
+        to_js(&self.inner.calculate_trend_score().map_err(to_js_error)?)
+    }
+
+    /// 指定時刻（UNIXミリ秒）を現在時刻としてスコアを計算
+    pub fn calculate_trend_score_at(&self, now_ms: f64) -> Result<JsValue, JsError> {
+        to_js(&self.inner.calculate_trend_score_at(now_ms as u64).map_err(to_js_error)?)
+    }
+}
+
+/// JavaScriptから利用する複数投稿・複数期間のトレンド計算のレジストリ
+#[wasm_bindgen(js_name = TrendEngine)]
+pub struct WasmTrendEngine {
+    inner: TrendEngine,
+}
+
+#[wasm_bindgen(js_class = TrendEngine)]
+impl WasmTrendEngine {
+    /// 新しいレジストリを作成
+    ///
+    /// `periods`は期間の配列（JSON、省略時は日次・週次・月次・年次）。
+    #[wasm_bindgen(constructor)]
+    pub fn new(periods_json: Option<String>) -> Result<WasmTrendEngine, JsError> {
+        let mut inner = TrendEngine::new().with_config(shared_config());
+        if let Some(json) = periods_json {
+            let periods: Vec<Period> = parse_json(0, "期間の配列", &json)?;
+            inner = inner.with_periods(&periods);
+        }
+        Ok(WasmTrendEngine { inner })
+    }
+
+    /// 現在時刻を固定する（バックフィル用、UNIXミリ秒）
+    pub fn set_fixed_now(&mut self, now_ms: f64) {
+        self.inner.set_clock(FixedClock(now_ms as u64));
+    }
+
+    /// 現在時刻をシステム時刻に戻す
+    pub fn use_system_clock(&mut self) {
+        self.inner.set_clock(SystemClock);
+    }
+
+    /// 投稿を登録する（登録済みならfalse）
+    pub fn add_post(&mut self, post_id: u32) -> bool {
+        self.inner.add_post(post_id)
+    }
+
+    /// 投稿とそのデータを削除する（未登録ならfalse）
+    pub fn remove_post(&mut self, post_id: u32) -> bool {
+        self.inner.remove_post(post_id)
+    }
+
+    /// 登録済みの投稿数
+    pub fn post_count(&self) -> u32 {
+        self.inner.post_count() as u32
+    }
+
+    /// 投稿のイベントの配列を取り込み、取り込んだ件数を返す
+    pub fn push_events(&mut self, post_id: u32, events_json: &str) -> Result<u32, JsError> {
+        let events: Vec<ViewEvent> = parse_json(post_id, "イベントデータ", events_json)?;
+        Ok(events.iter().filter(|event| self.inner.push_event(post_id, event)).count() as u32)
+    }
+
+    /// 投稿の集約済み時間窓の配列を取り込み、取り込んだ件数を返す
+    pub fn push_windows(&mut self, post_id: u32, windows_json: &str) -> Result<u32, JsError> {
+        let windows: Vec<WindowMetrics> = parse_json(post_id, "時間窓データ", windows_json)?;
+        let mut pushed = 0u32;
+        for window in windows {
+            if self.inner.push_window(post_id, window) {
+                pushed += 1;
+            }
+        }
+        Ok(pushed)
+    }
+
+    /// 投稿の疑わしさ（0〜1）を設定する（未登録ならfalse）
+    pub fn set_suspicion(&mut self, post_id: u32, suspicion: f64) -> bool {
+        self.inner.set_suspicion(post_id, suspicion)
+    }
+
+    /// 全投稿の指定時刻（UNIXミリ秒）より前のデータを破棄する
+    pub fn evict_before(&mut self, timestamp_ms: f64) {
+        self.inner.evict_before(timestamp_ms as u64);
+    }
+
+    /// 1投稿・1期間のスコアを計算（`now_ms`省略時は現在時刻）
+    pub fn score_post(&self, post_id: u32, period: JsValue, now_ms: Option<f64>) -> Result<JsValue, JsError> {
+        let period = parse_period(&period)?;
+        let stats = match now_ms {
+            Some(now) => self.inner.score_post_at(post_id, period, now as u64),
+            None => self.inner.score_post(post_id, period),
+        };
+        to_js(&stats.map_err(to_js_error)?)
+    }
+
+    /// 全期間のランキングを作成（`now_ms`省略時は現在時刻）
+    pub fn rank(&self, top_n: u32, now_ms: Option<f64>) -> Result<JsValue, JsError> {
+        let rankings = match now_ms {
+            Some(now) => self.inner.rank_at(top_n as usize, now as u64),
+            None => self.inner.rank(top_n as usize),
+        };
+        to_js(&rankings)
+    }
+}
+
+/// JavaScriptから利用するシリーズの急上昇スコア計算機
+#[wasm_bindgen(js_name = SeriesTrendCalculator)]
+pub struct WasmSeriesTrendCalculator {
+    inner: SeriesTrendCalculator,
+}
+
+#[wasm_bindgen(js_class = SeriesTrendCalculator)]
+impl WasmSeriesTrendCalculator {
+    /// 新しいシリーズ計算機を作成
+    #[wasm_bindgen(constructor)]
+    pub fn new(period: JsValue) -> Result<WasmSeriesTrendCalculator, JsError> {
+        let inner = SeriesTrendCalculator::new(parse_period(&period)?).with_config(shared_config());
+        Ok(WasmSeriesTrendCalculator { inner })
+    }
+
+    /// シリーズの急上昇スコアを計算
+    pub fn calculate(&self, series_json: &str) -> Result<JsValue, JsError> {
+        self.calculate_at(series_json, self.inner.now_ms() as f64)
+    }
+
+    /// 指定時刻（UNIXミリ秒）を現在時刻としてシリーズのスコアを計算
+    pub fn calculate_at(&self, series_json: &str, now_ms: f64) -> Result<JsValue, JsError> {
+        let data: SeriesTrendData = parse_json(0, "シリーズデータ", series_json)?;
+        let result = self.inner.calculate_at(&data, now_ms as u64).map_err(to_js_error)?;
+        to_js(&result)
+    }
+}
+
+/// JavaScriptから利用する重複閲覧の除外器
+#[wasm_bindgen(js_name = ViewDeduplicator)]
+pub struct WasmViewDeduplicator {
+    inner: ViewDeduplicator,
+}
+
+#[wasm_bindgen(js_class = ViewDeduplicator)]
+impl WasmViewDeduplicator {
+    /// 重複除外器を作成
+    ///
+    /// `config_json`は`{"expected_views": 100000, "false_positive_rate": 0.001, "window_ms": 60000}`形式。
+    /// 省略したフィールドは既定値になる。
+    #[wasm_bindgen(constructor)]
+    pub fn new(config_json: Option<String>) -> Result<WasmViewDeduplicator, JsError> {
+        let config: DedupConfig = match config_json {
+            Some(json) => parse_json(0, "重複除外設定", &json)?,
+            None => DedupConfig::default(),
+        };
+        let inner = ViewDeduplicator::new(config).map_err(to_js_error)?;
+        Ok(WasmViewDeduplicator { inner })
+    }
+
+    /// スナップショットから復元する
+    pub fn restore(snapshot: &[u8]) -> Result<WasmViewDeduplicator, JsError> {
+        let inner = ViewDeduplicator::restore(snapshot).map_err(to_js_error)?;
+        Ok(WasmViewDeduplicator { inner })
+    }
+
+    /// 閲覧を記録し、新規閲覧ならtrueを返す
+    ///
+    /// `user_key`はユーザーID、未ログインの場合はIPアドレスなど。
+    /// `now_ms`を省略した場合はシステム時刻を使う。
+    pub fn record_view(&mut self, post_id: u32, user_key: &str, now_ms: Option<f64>) -> bool {
+        match now_ms {
+            Some(now) => self.inner.record_at(post_id, user_key, now as u64),
+            None => self.inner.record(post_id, user_key),
+        }
+    }
+
+    /// 記録せずに重複とみなされるかを確認
+    pub fn is_duplicate(&self, post_id: u32, user_key: &str, now_ms: f64) -> bool {
+        self.inner.is_duplicate_at(post_id, user_key, now_ms as u64)
+    }
+
+    /// 総閲覧数・新規閲覧数・重複閲覧数
+    pub fn stats(&self) -> Result<JsValue, JsError> {
+        to_js(self.inner.stats())
+    }
+
+    /// 状態をバイト列として保存する（再起動時に`restore`で読み込む）
+    pub fn snapshot(&self) -> Vec<u8> {
+        self.inner.snapshot()
+    }
+}
+
+/// スコア設定をJSONから読み込み、以降に作成する全計算機で共有する
+///
+/// 省略したフィールドは既定値になる。不正な値や未知のフィールドがある場合は現在の設定を維持してエラーを投げる。
+#[wasm_bindgen]
+pub fn set_scoring_config(config_json: &str) -> Result<(), JsError> {
+    let config = ScoringConfig::from_json(config_json).map_err(to_js_error)?;
+    SCORING_CONFIG.with(|shared| *shared.borrow_mut() = Arc::new(config));
+    Ok(())
+}
+
+/// スコア設定を既定値に戻す
+#[wasm_bindgen]
+pub fn reset_scoring_config() {
+    SCORING_CONFIG.with(|shared| *shared.borrow_mut() = Arc::new(ScoringConfig::default()));
+}
+
+/// 現在のスコア設定を取得
+#[wasm_bindgen]
+pub fn get_scoring_config() -> Result<JsValue, JsError> {
+    to_js(&*shared_config())
+}
+
+/// 組み込みのランキングモデル（`'trend'`・`'hacker_news'`・`'reddit_hot'`・`'exponential_decay'`）で投稿をランキングする
+///
+/// `posts_json`は`[{"post_id": 1, "events": [...], "windows": [...], "published_at": 0}]`形式。
+/// 同じ入力でモデルを変えて呼び出すと、トレンドの定義を比べられる。
+#[wasm_bindgen]
+pub fn rank_with_model(posts_json: &str, model: &str, period: JsValue, top_n: u32, now_ms: Option<f64>) -> Result<JsValue, JsError> {
+    let model: ScoringModel = model.parse().map_err(to_js_error)?;
+    let period = parse_period(&period)?;
+    let posts: Vec<ScorerPost> = parse_json(0, "ランキング入力データ", posts_json)?;
+    let now = now_ms.map(|ms| ms as u64).unwrap_or_else(|| SystemClock.now_ms());
+    let scorer = model.scorer(shared_config());
+    to_js(&rank_with_scorer(scorer.as_ref(), &posts, period, top_n as usize, now))
+}
+
+/// 投稿の一覧からサイト全体のエンゲージメント率を求め、共有スコア設定の事前分布にする
+///
+/// `inputs_json`は`score_batch`と同じ形式。求めた率を返す。閲覧数の合計が0の場合は設定を変えない。
+#[wasm_bindgen]
+pub fn update_engagement_prior(inputs_json: &str) -> Result<JsValue, JsError> {
+    let inputs: Vec<BatchPostInput> = parse_json(0, "バッチ入力データ", inputs_json)?;
+    let counts: Vec<EngagementCounts> = inputs.iter().map(BatchPostInput::engagement_counts).collect();
+    if let Some(prior) = EngagementRates::from_posts(&counts) {
+        let mut config = ScoringConfig::clone(&shared_config());
+        config.engagement.prior = prior;
+        SCORING_CONFIG.with(|shared| *shared.borrow_mut() = Arc::new(config));
+    }
+    to_js(&shared_config().engagement.prior)
+}
+
+/// 複数投稿のスコアを一括計算し、スコア・内訳・上位N件のランキングを返す
+///
+/// `inputs_json`は`[{"post_id": 1, "direct": {...}}, {"post_id": 2, "hll": {...}}]`形式。
+/// `now_ms`を省略した場合はシステム時刻を使う。
+#[wasm_bindgen]
+pub fn score_batch(inputs_json: &str, period: JsValue, top_n: u32, now_ms: Option<f64>) -> Result<JsValue, JsError> {
+    let period = parse_period(&period)?;
+    let inputs: Vec<BatchPostInput> = parse_json(0, "バッチ入力データ", inputs_json)?;
+    let now = now_ms.map(|ms| ms as u64).unwrap_or_else(|| SystemClock.now_ms());
+    let result = BatchScorer::new(period, top_n as usize)
+        .with_config(shared_config())
+        .score_at(&inputs, now);
+    to_js(&result)
+}
+
+/// 投稿の総合スコアを一括計算し、スコア内訳と上位N件のランキングを返す
+///
+/// `inputs_json`は`[{"post_id": 1, "comment_count": 3, "bookmark_count": 2, "like_count": 10, "unique_viewers": 40}]`形式。
+#[wasm_bindgen]
+pub fn rank_total_posts(inputs_json: &str, top_n: u32) -> Result<JsValue, JsError> {
+    let inputs: Vec<TotalPostInput> = parse_json(0, "総合ランキング入力データ", inputs_json)?;
+    let calculator = TotalRankCalculator::new().with_config(shared_config());
+    to_js(&calculator.rank_posts(&inputs, top_n as usize))
+}
+
+/// シリーズの総合スコアを一括計算し、スコア内訳と上位N件のランキングを返す
+///
+/// `inputs_json`は`[{"series_id": 1, "follower_count": 12, "episode_count": 5, "average_post_score": 8.5, "is_completed": true}]`形式。
+#[wasm_bindgen]
+pub fn rank_total_series(inputs_json: &str, top_n: u32) -> Result<JsValue, JsError> {
+    let inputs: Vec<TotalSeriesInput> = parse_json(0, "シリーズ総合ランキング入力データ", inputs_json)?;
+    let calculator = TotalRankCalculator::new().with_config(shared_config());
+    to_js(&calculator.rank_series(&inputs, top_n as usize))
+}
+
+/// 閲覧レコードをパックする
+///
+/// `record_json`は`{"post_id": 1, "user_id": 2, "timestamp": 1749995251000, "device": 1, "country": 9}`形式。
+/// `format`は`'new'`（10バイト、既定）または`'v3'`（16バイト）。
+#[wasm_bindgen]
+pub fn pack_view(record_json: &str, format: Option<String>) -> Result<Vec<u8>, JsError> {
+    let record: ViewRecord = parse_json(0, "閲覧レコード", record_json)?;
+    let format: PackFormat = format.as_deref().unwrap_or("new").parse().map_err(to_js_error)?;
+    BinaryViewPacker::pack_all(&[record], format).map_err(to_js_error)
+}
+
+/// 1件分のバッファを復元する（9バイトは旧形式、10バイトは現行形式、16バイトはv3）
+#[wasm_bindgen]
+pub fn unpack_view(buffer: &[u8]) -> Result<JsValue, JsError> {
+    to_js(&BinaryViewPacker::unpack(buffer).map_err(to_js_error)?)
+}
+
+/// 同じ形式のレコードを連結したバッファを一括で復元する
+///
+/// `format`は`'old'`（9バイト）、`'new'`（10バイト）または`'v3'`（16バイト）。
+#[wasm_bindgen]
+pub fn unpack_views(buffer: &[u8], format: &str) -> Result<JsValue, JsError> {
+    let format: PackFormat = format.parse().map_err(to_js_error)?;
+    to_js(&BinaryViewPacker::unpack_all(buffer, format).map_err(to_js_error)?)
+}
+
+/// 保存済みの1件分のバッファをv3形式に書き換える
+#[wasm_bindgen]
+pub fn migrate_view(buffer: &[u8]) -> Result<Vec<u8>, JsError> {
+    let migrated = BinaryViewPacker::migrate(buffer).map_err(to_js_error)?;
+    Ok(migrated.to_vec())
+}
+
+/// 同じ形式のレコードを連結したバッファをv3形式に一括で書き換える
+#[wasm_bindgen]
+pub fn migrate_views(buffer: &[u8], format: &str) -> Result<Vec<u8>, JsError> {
+    let format: PackFormat = format.parse().map_err(to_js_error)?;
+    BinaryViewPacker::migrate_all(buffer, format).map_err(to_js_error)
+}
+
+/// 連結された閲覧データのバッファを投稿ごとに時間単位の窓に集約
+///
+/// `format`は`'old'`・`'new'`・`'v3'`のいずれか。
+#[wasm_bindgen]
+pub fn rollup_packed_views(buffer: &[u8], format: &str) -> Result<JsValue, JsError> {
+    let format: PackFormat = format.parse().map_err(to_js_error)?;
+    let records: Vec<ViewRecord> = BinaryViewPacker::unpack_all(buffer, format)
+        .map_err(to_js_error)?
+        .into_iter()
+        .map(|decoded| decoded.record)
+        .collect();
+    to_js(&rollup_events(&records))
+}
+
+/// 1段下の単位の窓を`level`（`'day'`・`'week'`・`'month'`・`'year'`）に集約
+///
+/// `level`を省略した場合は時間単位の窓から全単位を順に集約する。
+#[wasm_bindgen]
+pub fn rollup_time_windows(windows_json: &str, level: Option<String>) -> Result<JsValue, JsError> {
+    let windows: Vec<RollupWindow> = parse_json(0, "集約元の時間窓", windows_json)?;
+    match level {
+        Some(level) => {
+            let level: RollupLevel = level.parse().map_err(to_js_error)?;
+            to_js(&rollup_windows(&windows, level))
+        }
+        None => to_js(&rollup_all(&windows)),
+    }
+}
+
+/// 既存の窓と集約結果を比較し、作成・更新が必要な窓を返す
+#[wasm_bindgen]
+pub fn diff_time_windows(existing_json: &str, computed_json: &str) -> Result<JsValue, JsError> {
+    let existing: Vec<RollupWindow> = parse_json(0, "既存の時間窓", existing_json)?;
+    let computed: Vec<RollupWindow> = parse_json(0, "集約結果の時間窓", computed_json)?;
+    to_js(&diff_windows(&existing, &computed))
+}
+
+/// 閲覧レコード（JSON配列）から不正な閲覧を検出する
+#[wasm_bindgen]
+pub fn detect_abuse(records_json: &str) -> Result<JsValue, JsError> {
+    let records: Vec<ViewRecord> = parse_json(0, "閲覧レコード", records_json)?;
+    to_js(&AbuseDetector::new().with_config(shared_config()).detect(&records))
+}
+
+/// 連結された閲覧データのバッファから不正な閲覧を検出する
+#[wasm_bindgen]
+pub fn detect_abuse_packed(buffer: &[u8], format: &str) -> Result<JsValue, JsError> {
+    let format: PackFormat = format.parse().map_err(to_js_error)?;
+    let records: Vec<ViewRecord> = BinaryViewPacker::unpack_all(buffer, format)
+        .map_err(to_js_error)?
+        .into_iter()
+        .map(|decoded| decoded.record)
+        .collect();
+    to_js(&AbuseDetector::new().with_config(shared_config()).detect(&records))
+}
+
+/// 2つのスコアの内訳（`explanation`）を比べ、変化の大きい順に項と係数を返す
+#[wasm_bindgen]
+pub fn explain_score_changes(current_json: &str, previous_json: &str) -> Result<JsValue, JsError> {
+    let current: ScoreExplanation = parse_json(0, "現在のスコア内訳", current_json)?;
+    let previous: ScoreExplanation = parse_json(0, "前回のスコア内訳", previous_json)?;
+    to_js(&current.changes_since(&previous))
+}
+
+/// 時間ごとの閲覧数の系列（古い順、0で埋めたもの）から成長率・モメンタムを推定
+///
+/// 推定方法は共有設定の`trend.growth`に従う。設定を試す場合は`growth_json`で上書きする。
+#[wasm_bindgen]
+pub fn estimate_series_growth(series: &[u32], compare_hours: u32, growth_json: Option<String>) -> Result<JsValue, JsError> {
+    let mut config = ScoringConfig::clone(&shared_config());
+    if let Some(json) = growth_json {
+        config.trend.growth = parse_json(0, "成長率の推定設定", &json)?;
+        config.validate().map_err(to_js_error)?;
+    }
+    to_js(&estimate_growth(series, compare_hours as u64, &config.trend.growth))
+}
+
+/// ログの出力先を設定（`'noop'`・`'console'`・`'ring'`）
+///
+/// `'ring'`の場合は直近`capacity`件（既定1000件）を保持し、`read_log_buffer`で読み出せる。
+#[wasm_bindgen]
+pub fn set_log_output(kind: &str, capacity: Option<u32>) -> Result<(), JsError> {
+    let buffer = match kind.trim().to_ascii_lowercase().as_str() {
+        "noop" | "none" => {
+            set_log_sink(Rc::new(NoopSink));
+            None
+        }
+        "console" => {
+            set_log_sink(Rc::new(ConsoleSink));
+            None
+        }
+        "ring" | "buffer" => {
+            let buffer = Rc::new(RingBufferSink::new(capacity.unwrap_or(1000) as usize));
+            set_log_sink(buffer.clone());
+            Some(buffer)
+        }
+        _ => return Err(to_js_error(TrendError::parse("ログ出力先", format!("未知の出力先です: {}", kind)))),
+    };
+    LOG_BUFFER.with(|current| *current.borrow_mut() = buffer);
+    Ok(())
+}
+
+/// JSのコールバック関数をログの出力先に設定
+///
+/// 関数は`(post_id, action, message, data_json, level)`で呼び出される。
+#[wasm_bindgen]
+pub fn set_log_callback(callback: js_sys::Function) {
+    set_log_sink(Rc::new(JsCallbackSink { callback }));
+    LOG_BUFFER.with(|current| *current.borrow_mut() = None);
+}
+
+/// 出力する最低レベルを設定（`'trace'`〜`'error'`、`'off'`）
+#[wasm_bindgen]
+pub fn set_log_threshold(level: &str) -> Result<(), JsError> {
+    set_log_level(level.parse().map_err(to_js_error)?);
+    Ok(())
+}
+
+/// 投稿の計算過程の出力を切り替える
+#[wasm_bindgen]
+pub fn set_post_tracing(post_id: u32, enabled: bool) {
+    if enabled {
+        trace_post(post_id);
+    } else {
+        untrace_post(post_id);
+    }
+}
+
+/// 全投稿の計算過程の出力を切り替える
+#[wasm_bindgen]
+pub fn set_trace_all_posts(enabled: bool) {
+    trace_all_posts(enabled);
+}
+
+/// リングバッファのログを読み出す（`clear`がtrueなら読み出した分を消す）
+#[wasm_bindgen]
+pub fn read_log_buffer(clear: Option<bool>) -> Result<JsValue, JsError> {
+    let records = LOG_BUFFER.with(|buffer| match buffer.borrow().as_ref() {
+        Some(buffer) if clear.unwrap_or(false) => buffer.drain(),
+        Some(buffer) => buffer.records(),
+        None => Vec::new(),
+    });
+    to_js(&records)
+}
+
+/// ログの設定を既定（出力なし）に戻す
+#[wasm_bindgen]
+pub fn reset_log_settings() {
+    reset_logging();
+    LOG_BUFFER.with(|current| *current.borrow_mut() = None);
+}
+
+#[wasm_bindgen(start)]
+pub fn main() {
+    // WASM初期化時に実行される処理
+}
+
+/// 現在の出力先にテスト用のログを1件出力
+#[wasm_bindgen]
+pub fn test_log(post_id: u32, message: &str) {
+    log_event(LogLevel::Info, post_id, "test", message, || serde_json::json!({}));
+}
@@ -0,0 +1,164 @@
+//! ボット・不正閲覧の検出のテスト
+
+use std::sync::Arc;
+
+use trend_calculator::{
+    AbuseDetector, AbuseReason, FixedClock, Period, RedisHllData, ScoringConfig, TrendCalculator, TrendError,
+    ViewEvent, ViewRecord,
+};
+
+const SECOND: u64 = 1000;
+const NOW: u64 = 1_788_000_000_000;
+
+fn view(post_id: u32, user_id: u32, timestamp: u64) -> ViewRecord {
+    ViewRecord {
+        post_id,
+        user_id,
+        timestamp,
+        device: 1,
+        country: (user_id % 8) as u8,
+    }
+}
+
+/// 間隔が10秒と70秒を交互に繰り返す（均一ではない）閲覧
+fn irregular(count: u64) -> impl Iterator<Item = u64> {
+    (0..count).map(|i| NOW + (i / 2) * 80 * SECOND + (i % 2) * 10 * SECOND)
+}
+
+#[test]
+fn burst_views_beyond_limit_are_removed() {
+    let mut records: Vec<ViewRecord> = irregular(40).map(|ts| view(1, 500, ts)).collect();
+    records.extend((0..10).map(|user| view(1, user, NOW + user as u64 * SECOND)));
+
+    let report = AbuseDetector::new().detect(&records);
+
+    assert_eq!(report.cleaned.len(), 40);
+    assert_eq!(report.flagged_users.len(), 1);
+    let flagged = &report.flagged_users[0];
+    assert_eq!((flagged.user_id, flagged.post_id), (500, Some(1)));
+    assert_eq!(flagged.reason, AbuseReason::BurstRate);
+    assert_eq!(flagged.removed_views, 10);
+
+    let post = &report.posts[0];
+    assert_eq!((post.total_views, post.removed_views), (50, 10));
+    assert!((post.suspicion - 0.2).abs() < 1e-9);
+    assert_eq!(post.reasons, vec![AbuseReason::BurstRate]);
+}
+
+#[test]
+fn uniform_intervals_keep_only_first_view() {
+    let records: Vec<ViewRecord> = (0..8).map(|i| view(2, 600, NOW + i * 60 * SECOND)).collect();
+    let report = AbuseDetector::new().detect(&records);
+
+    assert_eq!(report.cleaned, vec![records[0]]);
+    assert_eq!(report.flagged_users[0].reason, AbuseReason::UniformIntervals);
+    assert_eq!(report.flagged_users[0].removed_views, 7);
+
+    // 人間らしいばらつきのある閲覧は除外しない
+    let human: Vec<ViewRecord> = irregular(8).map(|ts| view(2, 601, ts)).collect();
+    assert_eq!(AbuseDetector::new().detect(&human).cleaned.len(), 8);
+}
+
+#[test]
+fn many_posts_in_seconds_are_removed() {
+    let mut records: Vec<ViewRecord> = (0..8).map(|i| view(10 + i as u32, 700, NOW + i * SECOND)).collect();
+    // 同じユーザーでも時間を空けた閲覧は残す
+    records.push(view(10, 700, NOW + 3600 * SECOND));
+
+    let report = AbuseDetector::new().detect(&records);
+
+    assert_eq!(report.cleaned, vec![records[8]]);
+    assert_eq!(report.flagged_users[0].post_id, None);
+    assert_eq!(report.flagged_users[0].reason, AbuseReason::MultiPostSpree);
+    assert_eq!(report.flagged_users[0].removed_views, 8);
+    assert!(report.posts.iter().all(|p| p.reasons == vec![AbuseReason::MultiPostSpree]));
+}
+
+#[test]
+fn flag_cluster_raises_suspicion_without_removing_views() {
+    let mut records: Vec<ViewRecord> = (0..60)
+        .map(|user| ViewRecord {
+            device: 2,
+            country: 7,
+            ..view(5, 1000 + user, NOW + user as u64 * 97 * SECOND)
+        })
+        .collect();
+    records.extend((0..200).map(|user| view(6, 2000 + user, NOW + user as u64 * 89 * SECOND)));
+
+    let report = AbuseDetector::new().detect(&records);
+
+    assert_eq!(report.cleaned.len(), records.len());
+    assert!(report.flagged_users.is_empty());
+    let clustered = report.posts.iter().find(|p| p.post_id == 5).unwrap();
+    assert_eq!(clustered.reasons, vec![AbuseReason::FlagCluster]);
+    assert!((clustered.suspicion - 0.5).abs() < 1e-9);
+    let organic = report.posts.iter().find(|p| p.post_id == 6).unwrap();
+    assert_eq!(organic.suspicion, 0.0);
+}
+
+#[test]
+fn trend_score_discounts_bot_events_and_suspicion() {
+    let mut events: Vec<ViewEvent> = (0..20)
+        .map(|user| ViewEvent {
+            timestamp: NOW - 2 * 3600 * SECOND + user as u64 * 311 * SECOND,
+            user_id: user,
+            engagement_score: 1.0,
+            event_type: None,
+        })
+        .collect();
+    // 1分ごとに機械的に閲覧するボット
+    events.extend((0..120).map(|i| ViewEvent {
+        timestamp: NOW - 2 * 3600 * SECOND + i * 60 * SECOND,
+        user_id: 999,
+        engagement_score: 0.0,
+        event_type: None,
+    }));
+
+    let mut calc = TrendCalculator::new(1, Period::Daily).with_clock(FixedClock(NOW));
+    calc.set_recent_events(events.clone());
+    let raw = calc.calculate_trend_score().unwrap();
+    calc.set_abuse_filter(true);
+    let filtered = calc.calculate_trend_score().unwrap();
+
+    let detector = AbuseDetector::new();
+    let cleaned = detector.clean_events(&events);
+    assert_eq!(cleaned.events.len(), 21);
+    assert_eq!(cleaned.removed_events, 119);
+    assert!(filtered.score < raw.score);
+
+    // 外部から与えた疑わしさでスコアを割り引く
+    let hll = RedisHllData {
+        unique_users: 50,
+        view_count: 200,
+        previous_view_count: 150,
+        view_count_per_hour: 8.0,
+        like_count: 10,
+        comment_count: 2,
+        bookmark_count: 4,
+        last_activity_time: NOW,
+    };
+    let full = calc.calculate_with_redis_hll_data(&hll).unwrap().score;
+    calc.set_suspicion(0.5);
+    let discounted = calc.calculate_with_redis_hll_data(&hll).unwrap().score;
+    assert!((discounted - full / 2.0).abs() <= 0.01);
+}
+
+#[test]
+fn zero_windows_are_rejected_and_do_not_panic() {
+    for json in [
+        r#"{"abuse": {"burst_window_ms": 0}}"#,
+        r#"{"abuse": {"spree_window_ms": 0}}"#,
+        r#"{"abuse": {"max_views_per_burst": 0}}"#,
+        r#"{"abuse": {"uniform_min_views": 2}}"#,
+    ] {
+        assert!(matches!(ScoringConfig::from_json(json), Err(TrendError::Parse { .. })), "{}", json);
+    }
+
+    // 検証を通さずに組み立てた設定でも、期間0の窓で範囲外を参照しない
+    let mut config = ScoringConfig::default();
+    config.abuse.burst_window_ms = 0;
+    config.abuse.spree_window_ms = 0;
+    let detector = AbuseDetector::new().with_config(Arc::new(config));
+    let records = vec![view(1, 500, NOW), view(1, 500, NOW), view(2, 500, u64::MAX)];
+    assert_eq!(detector.detect(&records).cleaned.len(), 3);
+}
@@ -0,0 +1,99 @@
+//! 複数投稿の一括計算のテスト
+
+use trend_calculator::{
+    BatchComponents, BatchInputData, BatchPostInput, BatchScorer, DirectCalculationData, FixedClock, Period,
+    RedisHllData, TimeSkewPolicy, TrendCalculator, TrendError,
+};
+
+const HOUR: u64 = 60 * 60 * 1000;
+const NOW: u64 = 1_788_000_000_000;
+
+fn direct(post_id: u32, view_increase: u32, current_increase_rate: f64, last_updated: u64) -> BatchPostInput {
+    BatchPostInput {
+        post_id,
+        data: BatchInputData::Direct(DirectCalculationData {
+            view_increase,
+            unique_users: view_increase / 2,
+            like_increase: 3,
+            bookmark_count: 1,
+            comment_increase: 1,
+            previous_increase_rate: 2.0,
+            current_increase_rate,
+            total_views_all_time: 1000,
+            total_unique_users_all_time: 400,
+            last_updated,
+        }),
+        suspicion: 0.0,
+    }
+}
+
+fn hll(post_id: u32, view_count: u32) -> BatchPostInput {
+    BatchPostInput {
+        post_id,
+        data: BatchInputData::Hll(RedisHllData {
+            unique_users: view_count / 3,
+            view_count,
+            previous_view_count: view_count / 2,
+            view_count_per_hour: 5.0,
+            like_count: 4,
+            comment_count: 1,
+            bookmark_count: 2,
+            last_activity_time: NOW - HOUR,
+        }),
+        suspicion: 0.0,
+    }
+}
+
+#[test]
+fn mixed_inputs_are_scored_like_single_calculations_and_truncated() {
+    let inputs = vec![direct(1, 50, 3.0, NOW - HOUR), hll(2, 400), direct(3, 500, 3.0, NOW - HOUR), hll(4, 20)];
+    let result = BatchScorer::new(Period::Daily, 2).score_at(&inputs, NOW);
+    assert!(result.errors.is_empty());
+
+    // 入力順のスコアは単独の計算と一致し、内訳は入力の種類に合わせる
+    let calc = TrendCalculator::new(0, Period::Daily).with_clock(FixedClock(NOW));
+    let expected: Vec<f64> = inputs
+        .iter()
+        .map(|input| match &input.data {
+            BatchInputData::Direct(data) => calc.calculate_trending_score_direct(data).unwrap().score,
+            BatchInputData::Hll(data) => calc.calculate_with_redis_hll_data(data).unwrap().score,
+        })
+        .collect();
+    assert_eq!(result.scores.iter().map(|s| s.post_id).collect::<Vec<_>>(), [1, 2, 3, 4]);
+    assert_eq!(result.scores.iter().map(|s| s.score).collect::<Vec<_>>(), expected);
+    assert!(matches!(result.scores[0].components, BatchComponents::Direct(_)));
+    assert!(matches!(result.scores[1].components, BatchComponents::Hll(_)));
+
+    // 上位2件だけを1位から順に返す
+    let mut sorted = expected.clone();
+    sorted.sort_by(|a, b| b.total_cmp(a));
+    assert_eq!(result.ranking.len(), 2);
+    assert_eq!(result.ranking.iter().map(|r| r.rank).collect::<Vec<_>>(), [1, 2]);
+    assert_eq!(result.ranking.iter().map(|r| r.score).collect::<Vec<_>>(), sorted[..2]);
+}
+
+#[test]
+fn failed_posts_are_collected_as_errors() {
+    let inputs = vec![
+        direct(1, 50, 3.0, NOW - HOUR),
+        // 未来のタイムスタンプ（Rejectでは計算エラー）
+        direct(2, 50, 3.0, NOW + HOUR),
+        // 負の増加率は入力エラー
+        direct(3, 50, -2.0, NOW - HOUR),
+        hll(4, 100),
+    ];
+    let result = BatchScorer::new(Period::Weekly, 10)
+        .with_skew_policy(TimeSkewPolicy::Reject)
+        .score_at(&inputs, NOW);
+
+    assert_eq!(result.scores.iter().map(|s| s.post_id).collect::<Vec<_>>(), [1, 4]);
+    let mut ranked: Vec<u32> = result.ranking.iter().map(|r| r.post_id).collect();
+    ranked.sort();
+    assert_eq!(ranked, [1, 4]);
+
+    assert_eq!(result.errors.len(), 2);
+    assert_eq!(result.errors[0].post_id, 2);
+    assert!(matches!(result.errors[0].error, TrendError::FutureTimestamp { .. }));
+    assert_eq!(result.errors[1].post_id, 3);
+    assert!(matches!(&result.errors[1].error, TrendError::Parse { message, .. } if message.contains("current_increase_rate")));
+}
@@ -0,0 +1,113 @@
+//! スコア設定の読み込みと検証のテスト
+
+use std::sync::Arc;
+
+use trend_calculator::{FixedClock, GrowthEstimator, Period, RedisHllData, ScoringConfig, TrendCalculator, TrendError};
+
+#[test]
+fn default_config_round_trips_through_json() {
+    let default = ScoringConfig::default();
+    assert!(default.validate().is_ok());
+    assert_eq!(ScoringConfig::from_json("{}").unwrap(), default);
+
+    let mut config = ScoringConfig::default();
+    config.direct.decay_rates.weekly = 0.07;
+    config.hll.component_weights.yearly.growth = 0.6;
+    config.trend.growth.estimator = GrowthEstimator::LogLinear;
+    let json = serde_json::to_string(&config).unwrap();
+    assert_eq!(ScoringConfig::from_json(&json).unwrap(), config);
+}
+
+#[test]
+fn partial_overrides_keep_other_defaults() {
+    let config = ScoringConfig::from_json(
+        r#"{
+            "direct": {"decay_rates": {"daily": 0.2}},
+            "hll": {"component_weights": {"weekly": {"views": 0.5}}},
+            "trend": {"freshness_boost": 0.8}
+        }"#,
+    )
+    .unwrap();
+    let default = ScoringConfig::default();
+
+    // 期間ごとの表も、書いた期間だけが変わる
+    assert_eq!(config.direct.decay_rates.daily, 0.2);
+    assert_eq!(config.direct.decay_rates.weekly, default.direct.decay_rates.weekly);
+    assert_eq!(config.direct.momentum_weights, default.direct.momentum_weights);
+
+    let weekly = config.hll.component_weights.weekly;
+    assert_eq!(weekly.views, 0.5);
+    assert_eq!(weekly.growth, default.hll.component_weights.weekly.growth);
+    assert_eq!(config.hll.component_weights.daily, default.hll.component_weights.daily);
+
+    assert_eq!(config.trend.freshness_boost, 0.8);
+    assert_eq!(config.trend.decay_rates, default.trend.decay_rates);
+    assert_eq!(config.series, default.series);
+}
+
+#[test]
+fn invalid_values_are_rejected() {
+    for (json, field) in [
+        (r#"{"direct": {"decay_rates": {"monthly": -0.1}}}"#, "direct.decay_rates"),
+        (r#"{"hll": {"component_weights": {"daily": {"growth": -1}}}}"#, "hll.component_weights"),
+        (r#"{"direct": {"min_previous_rate": 0}}"#, "direct.min_previous_rate"),
+        (r#"{"trend": {"growth": {"count_prior": 0}}}"#, "trend.growth.count_prior"),
+    ] {
+        let error = ScoringConfig::from_json(json).unwrap_err();
+        assert!(matches!(&error, TrendError::Parse { message, .. } if message.contains(field)), "{:?}", error);
+    }
+
+    // 型の誤り・JSONの構文エラー
+    for json in [r#"{"direct": {"decay_rates": {"daily": "fast"}}}"#, r#"{"direct": "#, "[]"] {
+        assert!(matches!(ScoringConfig::from_json(json), Err(TrendError::Parse { .. })), "{}", json);
+    }
+
+    // 綴り誤りや存在しないフィールドは既定値のまま黙って無視せずエラーにする
+    for (json, field) in [
+        (r#"{"hll": {"momentum_scael": 9}}"#, "hll.momentum_scael"),
+        (r#"{"direct": {"decay_rates": {"hourly": 0.1}}}"#, "direct.decay_rates.hourly"),
+        (r#"{"abuze": {}}"#, "abuze"),
+    ] {
+        let error = ScoringConfig::from_json(json).unwrap_err();
+        assert!(matches!(&error, TrendError::Parse { message, .. } if message.ends_with(field)), "{:?}", error);
+    }
+
+    let mut config = ScoringConfig::default();
+    config.scales.momentum = f64::NAN;
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn hll_momentum_floor_is_off_by_default() {
+    const NOW: u64 = 1_788_000_000_000;
+    let data = RedisHllData {
+        unique_users: 2,
+        view_count: 3,
+        previous_view_count: 2,
+        view_count_per_hour: 0.001,
+        like_count: 0,
+        comment_count: 0,
+        bookmark_count: 0,
+        last_activity_time: NOW,
+    };
+    let momentum = |config: ScoringConfig| {
+        TrendCalculator::new(0, Period::Daily)
+            .with_clock(FixedClock(NOW))
+            .with_config(Arc::new(config))
+            .calculate_with_redis_hll_data(&data)
+            .unwrap()
+            .momentum
+    };
+
+    // 既定では下限がなく、log10(0.001) * 0.5 = -1.5のまま
+    assert_eq!(ScoringConfig::default().hll.momentum_floor, None);
+    assert!((momentum(ScoringConfig::default()) + 1.5).abs() < 1e-12);
+
+    let floored = ScoringConfig::from_json(r#"{"hll": {"momentum_floor": -1.0}}"#).unwrap();
+    assert_eq!(floored.hll.momentum_floor, Some(-1.0));
+    assert_eq!(momentum(floored), -1.0);
+
+    for json in [r#"{"hll": {"momentum_floor": 3.0}}"#, r#"{"hll": {"momentum_floor": "low"}}"#] {
+        assert!(matches!(ScoringConfig::from_json(json), Err(TrendError::Parse { .. })), "{}", json);
+    }
+}
@@ -0,0 +1,109 @@
+//! 重複閲覧の除外のテスト
+
+use trend_calculator::{DedupConfig, TrendError, ViewDeduplicator};
+
+const MINUTE: u64 = 60 * 1000;
+const NOW: u64 = 1_788_000_000_000;
+
+fn deduplicator() -> ViewDeduplicator {
+    ViewDeduplicator::new(DedupConfig {
+        expected_views: 10_000,
+        false_positive_rate: 0.001,
+        window_ms: MINUTE,
+    })
+    .unwrap()
+}
+
+#[test]
+fn repeated_views_within_window_are_duplicates() {
+    let mut dedup = deduplicator();
+
+    assert!(dedup.record_at(1, "user:10", NOW));
+    assert!(!dedup.record_at(1, "user:10", NOW + 30_000));
+    // 別の投稿・別のユーザーは独立して判定する
+    assert!(dedup.record_at(2, "user:10", NOW + 30_000));
+    assert!(dedup.record_at(1, "192.0.2.1", NOW + 30_000));
+
+    assert_eq!(dedup.stats().total_views, 4);
+    assert_eq!(dedup.stats().unique_views, 3);
+    assert_eq!(dedup.stats().duplicate_views, 1);
+}
+
+#[test]
+fn rotation_keeps_views_for_at_least_one_window() {
+    let mut dedup = deduplicator();
+    dedup.record_at(1, "user:10", NOW);
+    // 世代の終わり間際の閲覧も、世代が替わった直後は重複のまま
+    dedup.record_at(1, "user:20", NOW + MINUTE - 1);
+    assert!(dedup.is_duplicate_at(1, "user:20", NOW + MINUTE + 1));
+    assert!(!dedup.record_at(1, "user:20", NOW + MINUTE + 1));
+
+    // 2世代経過すると再び新規として数える
+    assert!(dedup.record_at(1, "user:10", NOW + 2 * MINUTE));
+    assert!(!dedup.is_duplicate_at(1, "user:20", NOW + 10 * MINUTE));
+}
+
+#[test]
+fn false_positive_rate_stays_near_target() {
+    let mut dedup = deduplicator();
+    for user in 0..10_000 {
+        dedup.record_at(1, &format!("user:{}", user), NOW);
+    }
+
+    let false_positives = (10_000..110_000)
+        .filter(|user| dedup.is_duplicate_at(1, &format!("user:{}", user), NOW))
+        .count();
+    assert!(false_positives < 300, "{}", false_positives);
+}
+
+#[test]
+fn snapshot_restores_state_across_restart() {
+    let mut dedup = deduplicator();
+    dedup.record_at(1, "user:10", NOW);
+    dedup.record_at(1, "user:20", NOW + MINUTE + 5);
+
+    let mut restored = ViewDeduplicator::restore(&dedup.snapshot()).unwrap();
+    assert_eq!(restored.stats(), dedup.stats());
+    assert_eq!(restored.config(), dedup.config());
+    assert!(!restored.record_at(1, "user:10", NOW + MINUTE + 10));
+    assert!(!restored.record_at(1, "user:20", NOW + MINUTE + 10));
+
+    let mut corrupted = dedup.snapshot();
+    corrupted.truncate(corrupted.len() - 8);
+    assert!(matches!(ViewDeduplicator::restore(&corrupted), Err(TrendError::InvalidSnapshot { .. })));
+}
+
+#[test]
+fn invalid_config_is_rejected() {
+    for config in [
+        DedupConfig { false_positive_rate: 0.0, ..DedupConfig::default() },
+        DedupConfig { false_positive_rate: 1.5, ..DedupConfig::default() },
+        DedupConfig { expected_views: 0, ..DedupConfig::default() },
+        DedupConfig { window_ms: 0, ..DedupConfig::default() },
+        // フィルターが大きすぎる設定
+        DedupConfig { expected_views: u64::MAX, ..DedupConfig::default() },
+        DedupConfig { expected_views: 1_000_000_000_000, ..DedupConfig::default() },
+    ] {
+        assert!(matches!(ViewDeduplicator::new(config), Err(TrendError::Parse { .. })));
+    }
+}
+
+#[test]
+fn oversized_or_truncated_snapshots_are_rejected_before_allocation() {
+    let snapshot = deduplicator().snapshot();
+    let with_expected_views = |expected_views: u64| {
+        let mut bytes = snapshot[..72].to_vec();
+        bytes[8..16].copy_from_slice(&expected_views.to_le_bytes());
+        bytes
+    };
+
+    // ヘッダーだけで巨大な設定を指定しても、確保や桁あふれをせずにエラーになる
+    for expected_views in [u64::MAX, 1_000_000_000_000] {
+        let result = ViewDeduplicator::restore(&with_expected_views(expected_views));
+        assert!(matches!(result, Err(TrendError::InvalidSnapshot { .. })), "{}", expected_views);
+    }
+
+    // 設定は正しくてもフィルター部分が欠けていればエラー
+    let result = ViewDeduplicator::restore(&snapshot[..72]);
+    assert!(matches!(result, Err(TrendError::InvalidSnapshot { message }) if message.contains("サイズ")));
+}
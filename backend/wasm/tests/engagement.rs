@@ -0,0 +1,123 @@
+//! エンゲージメント率の平滑化のテスト
+
+use std::sync::Arc;
+use trend_calculator::{
+    smooth_rates, BatchPostInput, EngagementConfig, EngagementCounts, EngagementModel, EngagementRates,
+    FixedClock, Period, RedisHllData, ScoringConfig, TrendCalculator,
+};
+
+const NOW: u64 = 1_788_000_000_000;
+
+fn counts(views: u32, likes: u32, comments: u32, bookmarks: u32) -> EngagementCounts {
+    EngagementCounts {
+        views,
+        likes,
+        comments,
+        bookmarks,
+    }
+}
+
+fn config(model: EngagementModel) -> EngagementConfig {
+    EngagementConfig {
+        model,
+        ..EngagementConfig::default()
+    }
+}
+
+fn hll_data(view_count: u32, bookmark_count: u32) -> RedisHllData {
+    RedisHllData {
+        unique_users: view_count,
+        view_count,
+        previous_view_count: view_count,
+        view_count_per_hour: 1.0,
+        like_count: 0,
+        comment_count: 0,
+        bookmark_count,
+        last_activity_time: NOW,
+    }
+}
+
+#[test]
+fn prior_is_pooled_over_posts() {
+    let posts = [counts(100, 10, 1, 4), counts(300, 10, 3, 0), counts(0, 0, 0, 0)];
+    let prior = EngagementRates::from_posts(&posts).unwrap();
+    assert_eq!(prior, EngagementRates { like: 0.05, comment: 0.01, bookmark: 0.01 });
+    assert_eq!(EngagementRates::from_posts(&[counts(0, 1, 0, 0)]), None);
+    assert_eq!(EngagementRates::from_posts(&[]), None);
+}
+
+#[test]
+fn raw_model_keeps_plain_ratios() {
+    let rates = smooth_rates(&counts(2, 1, 0, 1), &config(EngagementModel::Raw));
+    assert_eq!(rates, EngagementRates { like: 0.5, comment: 0.0, bookmark: 0.5 });
+    assert_eq!(smooth_rates(&counts(0, 0, 0, 0), &config(EngagementModel::Raw)).like, 0.0);
+}
+
+#[test]
+fn small_samples_are_pulled_toward_the_prior() {
+    let prior = EngagementRates::default().bookmark;
+    for model in [EngagementModel::BayesianAverage, EngagementModel::WilsonLowerBound] {
+        let config = config(model);
+        // 同じ本棚追加率50%でも、閲覧数が少ないほど全体の率に近い
+        let tiny = smooth_rates(&counts(2, 0, 0, 1), &config).bookmark;
+        let large = smooth_rates(&counts(2000, 0, 0, 1000), &config).bookmark;
+        assert!(tiny < 0.1, "{:?}: {}", model, tiny);
+        assert!(large > 0.45, "{:?}: {}", model, large);
+        // 閲覧がなければ全体の率（Wilsonでは下限なので全体の率以下）
+        let empty = smooth_rates(&counts(0, 0, 0, 0), &config).bookmark;
+        assert!(empty <= prior + 1e-12 && empty >= 0.0);
+    }
+
+    // Wilsonの下限はベイズ平均以下
+    let sample = counts(40, 6, 2, 3);
+    let bayes = smooth_rates(&sample, &config(EngagementModel::BayesianAverage));
+    let wilson = smooth_rates(&sample, &config(EngagementModel::WilsonLowerBound));
+    assert!(wilson.like < bayes.like && wilson.comment < bayes.comment && wilson.bookmark < bayes.bookmark);
+}
+
+#[test]
+fn low_view_posts_no_longer_outrank_on_engagement() {
+    let engagement = |model, data: &RedisHllData| {
+        let mut scoring = ScoringConfig::default();
+        scoring.engagement.model = model;
+        TrendCalculator::new(1, Period::Daily)
+            .with_clock(FixedClock(NOW))
+            .with_config(Arc::new(scoring))
+            .calculate_with_redis_hll_data(data)
+            .unwrap()
+            .engagement
+    };
+    let tiny = hll_data(2, 1);
+    let popular = hll_data(500, 50);
+
+    assert!(engagement(EngagementModel::Raw, &tiny) > engagement(EngagementModel::Raw, &popular));
+    for model in [EngagementModel::BayesianAverage, EngagementModel::WilsonLowerBound] {
+        assert!(engagement(model, &tiny) < engagement(model, &popular), "{:?}", model);
+    }
+}
+
+#[test]
+fn prior_can_be_computed_from_batch_inputs() {
+    let json = r#"[
+        {"post_id": 1, "hll": {"unique_users": 80, "view_count": 100, "previous_view_count": 90,
+            "view_count_per_hour": 4.0, "like_count": 8, "comment_count": 2, "bookmark_count": 1,
+            "last_activity_time": 0}},
+        {"post_id": 2, "direct": {"view_increase": 100, "unique_users": 70, "like_increase": 2,
+            "bookmark_count": 3, "comment_increase": 0, "previous_increase_rate": 0.1,
+            "current_increase_rate": 0.2, "total_views_all_time": 1000,
+            "total_unique_users_all_time": 500, "last_updated": 0}}
+    ]"#;
+    let inputs: Vec<BatchPostInput> = serde_json::from_str(json).unwrap();
+    let posts: Vec<EngagementCounts> = inputs.iter().map(BatchPostInput::engagement_counts).collect();
+    assert_eq!(posts[1], counts(100, 2, 0, 3));
+
+    let prior = EngagementRates::from_posts(&posts).unwrap();
+    assert_eq!(prior, EngagementRates { like: 0.05, comment: 0.01, bookmark: 0.02 });
+
+    let scoring: ScoringConfig =
+        ScoringConfig::from_json(r#"{ "engagement": { "model": "wilson_lower_bound", "prior_views": 20 } }"#)
+            .unwrap();
+    assert_eq!(scoring.engagement.model, EngagementModel::WilsonLowerBound);
+    assert_eq!(scoring.engagement.wilson_z, 1.96);
+    assert!(ScoringConfig::from_json(r#"{ "engagement": { "prior_views": -1 } }"#).is_err());
+}
@@ -0,0 +1,106 @@
+//! 複数投稿・複数期間のレジストリのテスト
+
+use trend_calculator::{FixedClock, Period, StreamingTrendCalculator, TrendEngine, TrendError, ViewEvent};
+
+const HOUR: u64 = 60 * 60 * 1000;
+const NOW: u64 = 496_667 * HOUR;
+
+/// 投稿ごとに件数・期間の異なるイベント（投稿IDが大きいほど多い）
+fn events(post_id: u32) -> Vec<ViewEvent> {
+    (0..post_id as u64 * 40)
+        .map(|i| ViewEvent {
+            // 最大で約60日前まで
+            timestamp: NOW - (i * 37 % 1440) * HOUR - i % 13,
+            user_id: (i * 11 % (post_id as u64 * 15)) as u32,
+            engagement_score: (i % 4) as f64 * 0.3,
+            event_type: match i % 9 {
+                0 => Some("like".to_string()),
+                4 => Some("comment".to_string()),
+                _ => None,
+            },
+        })
+        .collect()
+}
+
+fn engine(post_ids: &[u32]) -> TrendEngine {
+    let mut engine = TrendEngine::new().with_clock(FixedClock(NOW));
+    for post_id in post_ids {
+        assert!(engine.add_post(*post_id));
+        for event in events(*post_id) {
+            assert!(engine.push_event(*post_id, &event));
+        }
+    }
+    engine
+}
+
+#[test]
+fn scores_match_streaming_calculator_for_every_period() {
+    let engine = engine(&[1, 2, 3]);
+    for post_id in [1, 2, 3] {
+        for period in Period::STANDARD {
+            let mut stream = StreamingTrendCalculator::new(post_id, period);
+            for event in events(post_id) {
+                stream.push_event(&event);
+            }
+            let expected = stream.calculate_trend_score_at(NOW).unwrap();
+            assert_eq!(engine.score_post(post_id, period).unwrap(), expected);
+        }
+    }
+}
+
+#[test]
+fn rank_produces_all_periods_in_one_pass() {
+    let engine = engine(&[1, 2, 3, 4]);
+    let rankings = engine.rank_at(3, NOW);
+
+    let periods: Vec<Period> = rankings.iter().map(|r| r.period).collect();
+    assert_eq!(periods, Period::STANDARD);
+    for ranking in &rankings {
+        assert!(ranking.errors.is_empty());
+        assert_eq!(ranking.ranking.len(), 3);
+        assert!(ranking.ranking.windows(2).all(|w| w[0].score >= w[1].score));
+        assert_eq!(ranking.ranking[0].rank, 1);
+        let top = &ranking.ranking[0];
+        assert_eq!(top.score, engine.score_post_at(top.post_id, ranking.period, NOW).unwrap().score);
+    }
+}
+
+#[test]
+fn posts_can_be_added_and_removed() {
+    let mut engine = engine(&[1, 2]);
+    assert!(!engine.add_post(1));
+    assert!(engine.add_post(5));
+    assert_eq!(engine.post_ids(), [1, 2, 5]);
+
+    assert!(engine.remove_post(2));
+    assert!(!engine.remove_post(2));
+    assert!(!engine.contains_post(2));
+    assert_eq!(engine.post_count(), 2);
+
+    let rankings = engine.rank_at(10, NOW);
+    assert!(rankings.iter().all(|r| r.ranking.iter().all(|p| p.post_id != 2)));
+    assert!(rankings.iter().all(|r| r.ranking.len() == 2));
+}
+
+#[test]
+fn unknown_posts_are_rejected() {
+    let mut engine = engine(&[1]);
+    assert!(!engine.push_event(9, &events(1)[0]));
+    assert!(!engine.set_suspicion(9, 0.5));
+    assert_eq!(
+        engine.score_post_at(9, Period::Daily, NOW),
+        Err(TrendError::UnknownPost { post_id: 9 })
+    );
+}
+
+#[test]
+fn periods_and_suspicion_are_configurable() {
+    let mut engine = engine(&[1, 2]).with_periods(&[Period::Weekly, Period::Daily, Period::Weekly]);
+    assert_eq!(engine.periods(), [Period::Weekly, Period::Daily]);
+
+    let before = engine.score_post_at(2, Period::Weekly, NOW).unwrap().score;
+    assert!(engine.set_suspicion(2, 1.0));
+    let after = engine.score_post_at(2, Period::Weekly, NOW).unwrap().score;
+    assert!(after < before);
+    assert_eq!(engine.rank_at(5, NOW).len(), 2);
+}
@@ -0,0 +1,109 @@
+//! スコアの内訳のテスト
+
+use trend_calculator::{
+    DirectCalculationData, FixedClock, Period, RedisHllData, ScoreExplanation, TrendCalculator,
+    ViewEvent,
+};
+
+const HOUR: u64 = 60 * 60 * 1000;
+const NOW: u64 = 1_788_000_000_000;
+
+fn calculator() -> TrendCalculator {
+    TrendCalculator::new(1, Period::Daily).with_clock(FixedClock(NOW))
+}
+
+fn hll_data(view_count: u32) -> RedisHllData {
+    RedisHllData {
+        unique_users: 50,
+        view_count,
+        previous_view_count: 150,
+        view_count_per_hour: 8.0,
+        like_count: 10,
+        comment_count: 2,
+        bookmark_count: 4,
+        last_activity_time: NOW - 2 * HOUR,
+    }
+}
+
+fn contribution_sum(explanation: &ScoreExplanation) -> f64 {
+    explanation.terms.iter().map(|t| t.contribution).sum()
+}
+
+#[test]
+fn trend_score_explanation_adds_up_to_score() {
+    let mut calc = calculator();
+    calc.set_recent_events(
+        (0..40)
+            .map(|i| ViewEvent {
+                timestamp: NOW - 5 * HOUR + i * 7 * 60 * 1000,
+                user_id: (i % 25) as u32,
+                engagement_score: 0.5,
+                event_type: if i % 4 == 0 { Some("like".to_string()) } else { None },
+            })
+            .collect(),
+    );
+    let stats = calc.calculate_trend_score().unwrap();
+    let explanation = &stats.explanation;
+
+    let names: Vec<&str> = explanation.terms.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, ["views", "unique_users", "growth", "momentum", "engagement"]);
+    assert_eq!(explanation.term_named("views").unwrap().value, 40.0);
+    assert_eq!(explanation.term_named("unique_users").unwrap().value, stats.unique_users as f64);
+    assert!(explanation.factor_named("quality_multiplier").unwrap() > 1.0);
+    assert_eq!(explanation.factor_named("suspicion"), Some(1.0));
+
+    assert!((contribution_sum(explanation) - explanation.score).abs() < 1e-9);
+    assert_eq!((explanation.score * 100.0).round() / 100.0, stats.score);
+}
+
+#[test]
+fn direct_explanation_matches_result() {
+    let result = calculator()
+        .calculate_trending_score_direct(&DirectCalculationData {
+            view_increase: 120,
+            unique_users: 80,
+            like_increase: 6,
+            bookmark_count: 3,
+            comment_increase: 2,
+            previous_increase_rate: 2.0,
+            current_increase_rate: 5.0,
+            total_views_all_time: 1000,
+            total_unique_users_all_time: 400,
+            last_updated: NOW - HOUR,
+        })
+        .unwrap();
+    let explanation = &result.explanation;
+
+    assert!((explanation.base_score - result.base_score).abs() < 1e-9);
+    assert!((explanation.score - result.score).abs() < 1e-9);
+    assert_eq!(explanation.factor_named("time_decay"), Some(result.time_decay));
+    assert_eq!(explanation.factor_named("diversity"), Some(result.diversity_factor));
+    assert!((contribution_sum(explanation) - result.score).abs() < 1e-9);
+}
+
+#[test]
+fn hll_explanation_matches_result() {
+    let stats = calculator().calculate_with_redis_hll_data(&hll_data(200)).unwrap();
+    let explanation = &stats.explanation;
+
+    assert_eq!(explanation.term_named("views").unwrap().value, 200.0);
+    assert_eq!(explanation.term_named("growth").unwrap().value, stats.growth_rate + 1.0);
+    assert!((contribution_sum(explanation) - explanation.score).abs() < 1e-9);
+    assert_eq!((explanation.score * 100.0).round() / 100.0, stats.score);
+}
+
+#[test]
+fn changes_since_lists_biggest_drop_first() {
+    let calc = calculator();
+    let before = calc.calculate_with_redis_hll_data(&hll_data(400)).unwrap().explanation;
+    let after = calc.calculate_with_redis_hll_data(&hll_data(100)).unwrap().explanation;
+
+    let changes = after.changes_since(&before);
+    assert_eq!(changes[0].name, "growth");
+    assert!(changes[0].change < 0.0);
+    let views = changes.iter().find(|c| c.name == "views").unwrap();
+    assert!(views.change < 0.0);
+    let decay = changes.iter().find(|c| c.name == "time_decay").unwrap();
+    assert_eq!(decay.change, 0.0);
+    assert!(changes.windows(2).all(|w| w[0].change.abs() >= w[1].change.abs()));
+}
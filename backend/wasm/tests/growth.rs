@@ -0,0 +1,113 @@
+//! 成長率・モメンタムの推定のテスト
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use trend_calculator::{
+    estimate_growth, zero_filled_hours, FixedClock, GrowthConfig, GrowthEstimator, Period, ScoringConfig,
+    TrendCalculator, ViewEvent,
+};
+
+const HOUR: u64 = 60 * 60 * 1000;
+const NOW: u64 = 496_667 * HOUR;
+
+fn config(estimator: GrowthEstimator) -> GrowthConfig {
+    GrowthConfig {
+        estimator,
+        ..GrowthConfig::default()
+    }
+}
+
+/// 閲覧の少ない投稿の時間ごとの閲覧数（0〜5回がまばらに発生）
+fn sparse_series(hours: usize) -> Vec<u32> {
+    (0..hours as u64).map(|i| [0, 1, 0, 0, 5, 0, 1, 2, 0, 0, 1, 0, 4][(i * 7 % 13) as usize]).collect()
+}
+
+/// 1時間ずつずらした各時点の推定値の、隣り合う時点どうしの変化の最大値（成長率, モメンタム）
+fn max_hourly_jump(estimator: GrowthEstimator) -> (f64, f64) {
+    let series = sparse_series(200);
+    let config = config(estimator);
+    let estimates: Vec<_> = (8..=series.len()).map(|end| estimate_growth(&series[end - 8..end], 4, &config)).collect();
+    estimates.windows(2).fold((0.0, 0.0), |(growth, momentum), w| {
+        (
+            f64::max(growth, (w[1].growth_rate.clamp(-1.0, 2.0) - w[0].growth_rate.clamp(-1.0, 2.0)).abs()),
+            f64::max(momentum, (w[1].momentum - w[0].momentum).abs()),
+        )
+    })
+}
+
+#[test]
+fn hours_without_views_are_zero_filled() {
+    let counts: HashMap<u64, u32> = [(90, 3), (97, 2), (99, 4), (101, 1), (80, 9)].into_iter().collect();
+    // 現在より後の時間は現在の時間に含め、範囲より前の時間は捨てる
+    assert_eq!(zero_filled_hours(&counts, 100, 12), [0, 3, 0, 0, 0, 0, 0, 0, 2, 0, 4, 1]);
+}
+
+#[test]
+fn smoothed_estimators_are_stable_for_low_traffic_posts() {
+    let (block_growth, block_momentum) = max_hourly_jump(GrowthEstimator::BlockCompare);
+    for estimator in [GrowthEstimator::Ewma, GrowthEstimator::LogLinear] {
+        let (growth, momentum) = max_hourly_jump(estimator);
+        assert!(growth < block_growth / 2.0, "{:?}: {} / {}", estimator, growth, block_growth);
+        assert!(momentum < block_momentum / 2.0, "{:?}: {} / {}", estimator, momentum, block_momentum);
+    }
+}
+
+#[test]
+fn log_linear_recovers_exponential_growth() {
+    // 1時間あたり10%の増加
+    let series: Vec<u32> = (0..48).map(|i| (200.0 * 1.1f64.powi(i)).round() as u32).collect();
+    let estimate = estimate_growth(&series, 24, &config(GrowthEstimator::LogLinear));
+    assert!(estimate.confidence > 0.99);
+    assert!((estimate.momentum - 0.1).abs() < 0.01, "{:?}", estimate);
+    assert!((estimate.growth_rate - (1.1f64.powi(24) - 1.0)).abs() / estimate.growth_rate < 0.05, "{:?}", estimate);
+
+    let ewma = estimate_growth(&series, 24, &config(GrowthEstimator::Ewma));
+    assert!(ewma.growth_rate > 0.5 && ewma.momentum > 0.0 && ewma.confidence > 0.99, "{:?}", ewma);
+}
+
+#[test]
+fn flat_and_empty_series_have_no_growth() {
+    for estimator in [GrowthEstimator::BlockCompare, GrowthEstimator::Ewma, GrowthEstimator::LogLinear] {
+        for series in [vec![0u32; 8], vec![6u32; 8]] {
+            let estimate = estimate_growth(&series, 4, &config(estimator));
+            assert!(estimate.growth_rate.abs() < 1e-9, "{:?} {:?}", estimator, estimate);
+            assert!(estimate.momentum.abs() < 1e-9, "{:?} {:?}", estimator, estimate);
+        }
+    }
+    // 閲覧がなければ信頼度も0
+    assert_eq!(estimate_growth(&[0; 8], 4, &config(GrowthEstimator::LogLinear)).confidence, 0.0);
+}
+
+#[test]
+fn estimator_is_selected_from_config() {
+    let events: Vec<ViewEvent> = [0u64, 0, 1, 3, 3, 3, 3, 3]
+        .iter()
+        .enumerate()
+        .map(|(i, hours_ago)| ViewEvent {
+            timestamp: NOW - hours_ago * HOUR,
+            user_id: i as u32,
+            engagement_score: 0.5,
+            event_type: None,
+        })
+        .collect();
+    let growth_rate = |estimator| {
+        let mut scoring = ScoringConfig::default();
+        scoring.trend.growth.estimator = estimator;
+        let mut calc = TrendCalculator::new(1, Period::Daily)
+            .with_clock(FixedClock(NOW))
+            .with_config(Arc::new(scoring));
+        calc.set_recent_events(events.clone());
+        calc.calculate_trend_score().unwrap().growth_rate
+    };
+
+    // 従来の方法では前の4時間に閲覧がないため固定値の2.0
+    assert_eq!(growth_rate(GrowthEstimator::BlockCompare), 2.0);
+    let log_linear = growth_rate(GrowthEstimator::LogLinear);
+    assert!(log_linear > 0.0 && log_linear < 2.0, "{}", log_linear);
+
+    let json = r#"{ "trend": { "growth": { "estimator": "ewma", "count_prior": 2.0 } } }"#;
+    let scoring = ScoringConfig::from_json(json).unwrap();
+    assert_eq!(scoring.trend.growth.estimator, GrowthEstimator::Ewma);
+    assert_eq!(scoring.trend.growth.slow_half_life_ratio, 2.0);
+    assert!(ScoringConfig::from_json(r#"{ "trend": { "growth": { "count_prior": 0.0 } } }"#).is_err());
+}
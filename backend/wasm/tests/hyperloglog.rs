@@ -0,0 +1,144 @@
+//! HyperLogLogのテスト
+
+use trend_calculator::{
+    murmur_hash64a, FixedClock, HyperLogLog, Metrics, Period, TrendCalculator, ViewEvent, WindowMetrics,
+};
+
+const HOUR: u64 = 60 * 60 * 1000;
+const NOW: u64 = 1_788_000_000_000;
+
+fn sketch_of(users: impl IntoIterator<Item = u32>) -> HyperLogLog {
+    let mut sketch = HyperLogLog::new();
+    for user in users {
+        sketch.add_user(user);
+    }
+    sketch
+}
+
+#[test]
+fn counts_small_and_large_sets() {
+    assert_eq!(HyperLogLog::new().count(), 0);
+
+    let mut small = HyperLogLog::new();
+    for element in ["a", "b", "c", "d", "e", "f", "g"] {
+        assert!(small.add(element.as_bytes()));
+    }
+    assert!(!small.add(b"a"));
+    assert_eq!(small.count(), 7);
+
+    let estimate = sketch_of(0..100_000).count() as f64;
+    assert!((estimate - 100_000.0).abs() / 100_000.0 < 0.02, "{}", estimate);
+}
+
+#[test]
+fn merge_is_a_true_union() {
+    let mut a = sketch_of(0..60_000);
+    let b = sketch_of(40_000..100_000);
+    a.merge(&b);
+
+    assert_eq!(a, sketch_of(0..100_000));
+}
+
+#[test]
+fn redis_encodings_round_trip() {
+    // 空のHLLはXZERO(16384)だけの疎表現（`PFADD key`直後のRedisの値と同じ）
+    let empty = HyperLogLog::new().to_redis_bytes();
+    assert_eq!(&empty[..5], b"HYLL\x01");
+    assert_eq!(&empty[16..], &[0x7f, 0xff]);
+    assert_eq!(HyperLogLog::from_redis_bytes(&empty).unwrap(), HyperLogLog::new());
+
+    let small = sketch_of(0..200);
+    let sparse = small.to_redis_bytes();
+    assert_eq!(sparse[4], 1);
+    assert_eq!(HyperLogLog::from_redis_bytes(&sparse).unwrap(), small);
+
+    let dense = small.to_redis_dense_bytes();
+    assert_eq!(dense.len(), 16 + 12_288);
+    assert_eq!(dense[4], 0);
+    assert_eq!(HyperLogLog::from_redis_bytes(&dense).unwrap(), small);
+
+    // 大きいスケッチは密表現になる
+    let large = sketch_of(0..50_000);
+    assert_eq!(large.to_redis_bytes(), large.to_redis_dense_bytes());
+
+    assert!(HyperLogLog::from_redis_bytes(b"HYLL\x00").is_err());
+    assert!(HyperLogLog::from_redis_bytes(&dense[..dense.len() - 1]).is_err());
+}
+
+// 既知の値は redis/src/hyperloglog.c（7.x）の`MurmurHash64A`・`hllPatLen`・疎表現を
+// 独立にPythonへ移植して求めたもの。実際のRedisでは次のように確認できる。
+//
+//   redis-cli PFADD hll a b c d e f g && redis-cli --no-raw GET hll
+#[test]
+fn murmur_hash_matches_known_answers() {
+    for (key, hash) in [
+        (&b""[..], 0xd8df_ea65_85bc_9732),
+        (b"a", 0x53d2_470a_9b43_b1a7),
+        (b"hello", 0x0f65_6f01_eecf_e400),
+        (b"abcdefgh", 0xf3a6_5df5_5991_4567),
+        (b"0123456789abcdef!", 0xb917_c99b_031f_7674),
+        (b"user:42", 0xdbe8_10b5_5d80_1b51),
+    ] {
+        assert_eq!(murmur_hash64a(key, 0xadc8_3b19), hash, "{:?}", key);
+    }
+}
+
+#[test]
+fn pfadd_bytes_match_known_answer() {
+    // `PFADD hll a b c d e f g`直後の値: 疎表現、キャッシュ済み件数は無効フラグ付き
+    let mut expected = b"HYLL\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x80".to_vec();
+    expected.extend_from_slice(&[
+        0x46, 0x6d, 0x80, 0x56, 0x0c, 0x80, 0x44, 0x3c, 0x84, 0x38, 0x80, 0x50, 0xb1, 0x84, 0x49, 0x8c, 0x80, 0x42,
+        0x6d, 0x80, 0x42, 0x5a,
+    ]);
+
+    let mut sketch = HyperLogLog::new();
+    for element in ["a", "b", "c", "d", "e", "f", "g"] {
+        sketch.add(element.as_bytes());
+    }
+    assert_eq!(sketch.to_redis_bytes(), expected);
+
+    let restored = HyperLogLog::from_redis_bytes(&expected).unwrap();
+    assert_eq!(restored, sketch);
+    assert_eq!(restored.count(), 7);
+}
+
+#[test]
+fn window_sketch_survives_json() {
+    let window = WindowMetrics {
+        start_time: NOW - HOUR,
+        end_time: NOW - 1,
+        metrics: Metrics { unique_users: 3, total_views: 5 },
+        sketch: Some(sketch_of([1, 2, 3])),
+    };
+    let json = serde_json::to_string(&window).unwrap();
+    let restored: WindowMetrics = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored, window);
+
+    // スケッチのない従来のJSONも読み込める
+    let legacy: WindowMetrics =
+        serde_json::from_str(r#"{"start_time":0,"end_time":1,"metrics":{"unique_users":1,"total_views":1}}"#).unwrap();
+    assert!(legacy.sketch.is_none());
+}
+
+#[test]
+fn trend_score_uses_sketch_union_across_windows_and_events() {
+    let windows = (1..=6)
+        .map(|h| WindowMetrics {
+            start_time: NOW - h * HOUR,
+            end_time: NOW - (h - 1) * HOUR - 1,
+            metrics: Metrics { unique_users: 100, total_views: 150 },
+            sketch: Some(sketch_of(0..100)),
+        })
+        .collect();
+    let events = (0..20)
+        .map(|i| ViewEvent { timestamp: NOW - 60_000 * i as u64, user_id: 90 + i, engagement_score: 1.0, event_type: None })
+        .collect();
+
+    let mut calc = TrendCalculator::new(1, Period::Daily).with_clock(FixedClock(NOW));
+    calc.set_aggregated_windows(windows);
+    calc.set_recent_events(events);
+
+    // 同じ100人が6時間見続け、最近のイベントで新たに10人（100〜109）が加わった
+    assert_eq!(calc.calculate_trend_score().unwrap().unique_users, 110);
+}
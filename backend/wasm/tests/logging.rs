@@ -0,0 +1,109 @@
+//! ログ出力先・レベル・投稿ごとの追跡のテスト
+//!
+//! ログの設定はスレッドごとなので、各テストは独立して設定を変更できる。
+
+use std::cell::Cell;
+use std::rc::Rc;
+use trend_calculator::{
+    log_enabled, log_event, reset_logging, set_log_level, set_log_sink, trace_all_posts, trace_post,
+    untrace_post, FixedClock, LogLevel, LogRecord, LogSink, Period, RingBufferSink, TrendCalculator,
+};
+
+const NOW: u64 = 1_788_000_000_000;
+
+fn ring(capacity: usize) -> Rc<RingBufferSink> {
+    reset_logging();
+    let buffer = Rc::new(RingBufferSink::new(capacity));
+    set_log_sink(buffer.clone());
+    buffer
+}
+
+fn calculate(post_id: u32) {
+    TrendCalculator::new(post_id, Period::Daily)
+        .with_clock(FixedClock(NOW))
+        .calculate_trend_score()
+        .unwrap();
+}
+
+#[test]
+fn calculation_steps_are_only_logged_for_traced_posts() {
+    let buffer = ring(100);
+
+    calculate(1);
+    assert!(buffer.is_empty());
+
+    trace_post(2);
+    calculate(1);
+    calculate(2);
+    let records = buffer.drain();
+    assert!(!records.is_empty());
+    assert!(records.iter().all(|r| r.post_id == 2 && r.level == LogLevel::Trace));
+    assert_eq!(records[0].action, "init");
+    assert!(records.iter().any(|r| r.action == "final_score" && r.data["final_score"].is_number()));
+
+    untrace_post(2);
+    calculate(2);
+    assert!(buffer.is_empty());
+
+    trace_all_posts(true);
+    calculate(3);
+    assert!(buffer.records().iter().any(|r| r.post_id == 3));
+}
+
+#[test]
+fn level_threshold_filters_events() {
+    let buffer = ring(100);
+
+    log_event(LogLevel::Info, 1, "info", "出力されない", || "data");
+    log_event(LogLevel::Warn, 1, "warn", "出力される", || "data");
+    set_log_level(LogLevel::Error);
+    log_event(LogLevel::Warn, 1, "warn", "出力されない", || "data");
+    set_log_level(LogLevel::Off);
+    log_event(LogLevel::Error, 1, "error", "出力されない", || "data");
+    assert!(!log_enabled(LogLevel::Off, 1));
+
+    let actions: Vec<String> = buffer.records().into_iter().map(|r| r.action).collect();
+    assert_eq!(actions, ["warn"]);
+    assert_eq!("WARNING".parse::<LogLevel>().unwrap(), LogLevel::Warn);
+    assert!("verbose".parse::<LogLevel>().is_err());
+}
+
+#[test]
+fn disabled_logs_do_not_build_data() {
+    reset_logging();
+    let built = Cell::new(false);
+    log_event(LogLevel::Debug, 1, "debug", "出力されない", || {
+        built.set(true);
+        "data"
+    });
+    assert!(!built.get());
+}
+
+#[test]
+fn ring_buffer_keeps_most_recent_records() {
+    let buffer = ring(3);
+    for post_id in 0..5 {
+        log_event(LogLevel::Error, post_id, "error", "失敗", || post_id);
+    }
+    let post_ids: Vec<u32> = buffer.records().iter().map(|r| r.post_id).collect();
+    assert_eq!(post_ids, [2, 3, 4]);
+    assert_eq!(buffer.drain().len(), 3);
+    assert!(buffer.is_empty());
+}
+
+#[test]
+fn custom_sinks_receive_records() {
+    struct Counter(Cell<u32>);
+    impl LogSink for Counter {
+        fn write(&self, record: &LogRecord) {
+            assert_eq!(record.data["count"], 2);
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    reset_logging();
+    let counter = Rc::new(Counter(Cell::new(0)));
+    set_log_sink(counter.clone());
+    log_event(LogLevel::Error, 1, "error", "失敗", || serde_json::json!({ "count": 2 }));
+    assert_eq!(counter.0.get(), 1);
+}
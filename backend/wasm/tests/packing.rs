@@ -0,0 +1,148 @@
+//! 閲覧データのバイナリパッキングのテスト
+//!
+//! v1・v2 の期待するバイト列と復元値は utils/binaryPacking.js の`pack`/`unpack`の出力。
+
+use trend_calculator::{BinaryViewPacker, PackFormat, TrendError, ViewRecord};
+
+// 2025-06-15T13:47:31Z
+const TIMESTAMP: u64 = 1_749_995_251_000;
+
+fn record() -> ViewRecord {
+    ViewRecord {
+        post_id: 0x12_3456,
+        user_id: 0xAB_CDEF,
+        timestamp: TIMESTAMP,
+        device: 1,
+        country: 9,
+    }
+}
+
+#[test]
+fn pack_matches_js_layout() {
+    let packed = BinaryViewPacker::pack(&record()).unwrap();
+    assert_eq!(packed, [0x12, 0x34, 0x56, 0xAB, 0xCD, 0xEF, 0xBA, 0xCD, 0x19, 0x2F]);
+
+    let base = ViewRecord { post_id: 1, user_id: 2, timestamp: 1_577_836_800_000, device: 0, country: 0 };
+    assert_eq!(BinaryViewPacker::pack(&base).unwrap(), [0, 0, 1, 0, 0, 2, 0, 0, 0, 0]);
+}
+
+#[test]
+fn unpack_v2_truncates_to_minute() {
+    let decoded = BinaryViewPacker::unpack(&BinaryViewPacker::pack(&record()).unwrap()).unwrap();
+    assert_eq!(decoded.format, PackFormat::V2);
+    assert_eq!(decoded.record, ViewRecord { timestamp: 1_749_995_220_000, ..record() });
+}
+
+#[test]
+fn unpack_v1_matches_js() {
+    let legacy = [0x12, 0x34, 0x56, 0xAB, 0xCD, 0xEF, 0x04, 0xD2, 0x29];
+    let decoded = BinaryViewPacker::unpack(&legacy).unwrap();
+    assert_eq!(decoded.format, PackFormat::V1);
+    assert_eq!(
+        decoded.record,
+        ViewRecord { post_id: 0x12_3456, user_id: 0xAB_CDEF, timestamp: 1_577_968_440_000, device: 2, country: 9 }
+    );
+}
+
+#[test]
+fn unpack_all_decodes_concatenated_records() {
+    let records: Vec<ViewRecord> = (0..5)
+        .map(|i| ViewRecord { post_id: i, user_id: 100 + i, timestamp: TIMESTAMP - i as u64 * 3_600_000, ..record() })
+        .collect();
+    let buffer = BinaryViewPacker::pack_all(&records, PackFormat::V2).unwrap();
+    assert_eq!(buffer.len(), 50);
+
+    let decoded = BinaryViewPacker::unpack_all(&buffer, PackFormat::V2).unwrap();
+    let post_ids: Vec<u32> = decoded.iter().map(|d| d.record.post_id).collect();
+    assert_eq!(post_ids, vec![0, 1, 2, 3, 4]);
+    assert_eq!(decoded[4].record.timestamp, 1_749_995_220_000 - 4 * 3_600_000);
+
+    assert_eq!(
+        BinaryViewPacker::unpack_all(&buffer[..45], PackFormat::V2),
+        Err(TrendError::InvalidBufferLength { length: 45, record_len: 10 })
+    );
+}
+
+#[test]
+fn pack_rejects_values_outside_bit_width() {
+    let cases = [
+        ("post_id", ViewRecord { post_id: 0x100_0000, ..record() }),
+        ("device", ViewRecord { device: 16, ..record() }),
+        ("timestamp", ViewRecord { timestamp: 1_577_836_799_999, ..record() }),
+    ];
+    for (field, invalid) in cases {
+        match BinaryViewPacker::pack(&invalid) {
+            Err(TrendError::InvalidViewRecord { field: f, .. }) => assert_eq!(f, field),
+            other => panic!("{}: {:?}", field, other),
+        }
+    }
+
+    let (_, end) = PackFormat::V2.valid_time_range();
+    assert!(BinaryViewPacker::pack(&ViewRecord { timestamp: end, ..record() }).is_ok());
+    assert!(BinaryViewPacker::pack(&ViewRecord { timestamp: end + 60_000, ..record() }).is_err());
+}
+
+#[test]
+fn time_range_ends_at_the_last_millisecond_of_the_last_minute() {
+    // 最後の分の59.999秒までは書き込め、分の先頭に切り捨てて読み出せる
+    let (_, end) = PackFormat::V2.valid_time_range();
+    assert_eq!(end % 60_000, 59_999);
+    let packed = BinaryViewPacker::pack(&ViewRecord { timestamp: end, ..record() }).unwrap();
+    assert_eq!(BinaryViewPacker::unpack(&packed).unwrap().record.timestamp, end - 59_999);
+    assert!(BinaryViewPacker::pack(&ViewRecord { timestamp: end + 1, ..record() }).is_err());
+
+    let (start, end) = PackFormat::V1.valid_time_range();
+    assert_eq!((end + 1 - start) % 60_000, 0);
+}
+
+#[test]
+fn unpack_rejects_bad_lengths_and_minutes() {
+    let error = BinaryViewPacker::unpack(&[0; 8]).unwrap_err();
+    assert_eq!(error, TrendError::UnknownRecordLength { length: 8, accepted: vec![9, 10, 16] });
+    assert_eq!(error.to_string(), "レコード長から形式を判別できません: length=8（9・10・16バイトのいずれか）");
+
+    let mut packed = BinaryViewPacker::pack(&record()).unwrap();
+    packed[9] = 60;
+    assert!(matches!(BinaryViewPacker::unpack(&packed), Err(TrendError::InvalidViewRecord { .. })));
+}
+
+#[test]
+fn v3_round_trips_full_width_values() {
+    let wide = ViewRecord {
+        post_id: u32::MAX,
+        user_id: 0x0100_0000,
+        timestamp: 1_900_000_000_123, // v2では表現できない2030年
+        device: 2,
+        country: 15,
+    };
+    let packed = BinaryViewPacker::pack_v3(&wide).unwrap();
+    assert_eq!(
+        packed,
+        [3, 0x2F, 0xFF, 0xFF, 0xFF, 0xFF, 0x01, 0x00, 0x00, 0x00, 0x01, 0xBA, 0x60, 0xD3, 0x38, 0x7B]
+    );
+    assert!(BinaryViewPacker::pack(&wide).is_err());
+
+    let decoded = BinaryViewPacker::unpack(&packed).unwrap();
+    assert_eq!(decoded.format, PackFormat::V3);
+    assert_eq!(decoded.record, wide);
+
+    let mut untagged = packed;
+    untagged[0] = 4;
+    assert!(matches!(BinaryViewPacker::unpack(&untagged), Err(TrendError::InvalidViewRecord { .. })));
+}
+
+#[test]
+fn migrate_rewrites_v1_and_v2_buffers_as_v3() {
+    let v2 = BinaryViewPacker::pack(&record()).unwrap();
+    let migrated = BinaryViewPacker::migrate(&v2).unwrap();
+    assert_eq!(BinaryViewPacker::unpack(&migrated).unwrap().record, BinaryViewPacker::unpack(&v2).unwrap().record);
+    assert_eq!(BinaryViewPacker::migrate(&migrated).unwrap(), migrated);
+
+    let v1 = [0x12, 0x34, 0x56, 0xAB, 0xCD, 0xEF, 0x04, 0xD2, 0x29, 0x00, 0x00, 0x01, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00];
+    let migrated = BinaryViewPacker::migrate_all(&v1, PackFormat::V1).unwrap();
+    assert_eq!(migrated.len(), 2 * 16);
+
+    let decoded = BinaryViewPacker::unpack_all(&migrated, PackFormat::V3).unwrap();
+    assert_eq!(decoded[0].record.timestamp, 1_577_968_440_000);
+    assert_eq!((decoded[1].record.post_id, decoded[1].record.user_id), (1, 2));
+}
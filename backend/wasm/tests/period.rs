@@ -0,0 +1,83 @@
+//! 集計期間の解析と標準期間への対応付けのテスト
+
+use trend_calculator::{Period, TrendError};
+
+const HOUR: u64 = 60 * 60 * 1000;
+const DAY: u64 = 24 * HOUR;
+
+fn parse(s: &str) -> Period {
+    s.parse().unwrap_or_else(|e| panic!("{}: {:?}", s, e))
+}
+
+#[test]
+fn standard_names_aliases_and_codes_are_parsed() {
+    for (names, period) in [
+        (["daily", "day", "0", " Daily "], Period::Daily),
+        (["weekly", "week", "1", "WEEK"], Period::Weekly),
+        (["monthly", "month", "2", "Monthly"], Period::Monthly),
+        (["yearly", "year", "3", "year "], Period::Yearly),
+    ] {
+        for name in names {
+            assert_eq!(parse(name), period, "{}", name);
+        }
+    }
+    for (code, period) in Period::STANDARD.iter().enumerate() {
+        assert_eq!(Period::from_type(code as u8).unwrap(), *period);
+        assert_eq!(period.type_index(), code as u8);
+    }
+    assert!(matches!(Period::from_type(4), Err(TrendError::InvalidPeriod { .. })));
+}
+
+#[test]
+fn custom_durations_are_parsed_and_round_trip() {
+    for (text, duration_ms) in [
+        ("last 6 hours", 6 * HOUR),
+        ("6 hours", 6 * HOUR),
+        ("6h", 6 * HOUR),
+        ("Last 1 Hour", HOUR),
+        ("last 3 days", 3 * DAY),
+        ("3d", 3 * DAY),
+        ("90 ms", 90),
+    ] {
+        let period = parse(text);
+        assert_eq!(period, Period::Custom { duration_ms }, "{}", text);
+        assert_eq!(parse(&period.to_string()), period);
+    }
+    assert_eq!(parse("3d").to_string(), "last 3 days");
+    assert_eq!(parse("36h").to_string(), "last 36 hours");
+    assert_eq!(parse("6h").hours(), 6.0);
+
+    // serdeでは文字列として読み書きする
+    assert_eq!(serde_json::to_string(&parse("3d")).unwrap(), r#""last 3 days""#);
+    assert_eq!(serde_json::from_str::<Period>(r#""day""#).unwrap(), Period::Daily);
+}
+
+#[test]
+fn invalid_and_zero_durations_are_rejected() {
+    for text in ["", "hourly", "last hours", "6", "4", "6 weeks", "-3d", "last 0 hours", "0d", "0 ms", "h6"] {
+        assert!(matches!(text.parse::<Period>(), Err(TrendError::InvalidPeriod { value }) if value == text), "{}", text);
+    }
+    // 桁あふれする長さ
+    assert!(format!("{}d", u64::MAX / DAY + 1).parse::<Period>().is_err());
+    assert!(Period::last_hours(0).is_err());
+    assert!(Period::last_days(u64::MAX).is_err());
+}
+
+#[test]
+fn custom_periods_use_the_shortest_standard_period_that_contains_them() {
+    for (period, base) in [
+        (Period::last_hours(6).unwrap(), Period::Daily),
+        (Period::last_hours(24).unwrap(), Period::Daily),
+        // 日次より長いので、長さが近い日次ではなく週次
+        (Period::last_hours(25).unwrap(), Period::Weekly),
+        (Period::last_days(7).unwrap(), Period::Weekly),
+        (Period::last_days(8).unwrap(), Period::Monthly),
+        (Period::last_days(30).unwrap(), Period::Monthly),
+        (Period::last_days(31).unwrap(), Period::Yearly),
+        (Period::last_days(1000).unwrap(), Period::Yearly),
+        (Period::Weekly, Period::Weekly),
+    ] {
+        assert_eq!(period.base(), base, "{}", period);
+    }
+    assert_eq!(Period::last_hours(25).unwrap().type_index(), 1);
+}
@@ -0,0 +1,74 @@
+//! 同点時の順位決定ポリシーのテスト
+
+use std::cmp::Ordering;
+use trend_calculator::{rank_top_n, RankedPost, RankingKey};
+
+fn key(post_id: u32, score: f64, unique_users: u32, last_activity: u64) -> RankingKey {
+    RankingKey {
+        post_id,
+        score,
+        unique_users,
+        last_activity,
+    }
+}
+
+fn order(keys: Vec<RankingKey>) -> Vec<u32> {
+    let len = keys.len();
+    rank_top_n(keys, len).iter().map(|r| r.post_id).collect()
+}
+
+#[test]
+fn each_tie_break_level_decides_only_on_equal_previous_levels() {
+    // 1. スコアの降順（他の項目が劣っていても優先）
+    assert_eq!(key(9, 10.5, 1, 1).cmp(&key(1, 10.0, 100, 100)), Ordering::Less);
+    // 2. 同点ならユニークユーザー数の降順
+    assert_eq!(key(9, 10.0, 5, 1).cmp(&key(1, 10.0, 4, 100)), Ordering::Less);
+    // 3. さらに同じなら最終アクティビティの新しい方
+    assert_eq!(key(9, 10.0, 5, 200).cmp(&key(1, 10.0, 5, 100)), Ordering::Less);
+    // 4. 最後は投稿IDの昇順
+    assert_eq!(key(1, 10.0, 5, 200).cmp(&key(9, 10.0, 5, 200)), Ordering::Less);
+    assert_eq!(key(1, 10.0, 5, 200).cmp(&key(1, 10.0, 5, 200)), Ordering::Equal);
+
+    let keys = vec![
+        key(4, 10.0, 5, 100),
+        key(3, 10.0, 5, 200),
+        key(2, 10.0, 7, 50),
+        key(5, 12.0, 1, 1),
+        key(1, 10.0, 5, 100),
+    ];
+    assert_eq!(order(keys), [5, 2, 3, 1, 4]);
+}
+
+#[test]
+fn nan_ranks_last_and_signed_zeros_tie() {
+    let keys = vec![
+        key(1, f64::NAN, 100, 100),
+        key(2, f64::NEG_INFINITY, 0, 0),
+        key(3, 1.0, 0, 0),
+        key(4, -f64::NAN, 50, 50),
+        key(5, f64::INFINITY, 0, 0),
+    ];
+    // NaNは-infより下、NaN同士は同点として次の基準で並ぶ
+    assert_eq!(order(keys), [5, 3, 2, 1, 4]);
+
+    // -0.0と0.0は同点なので、ユニークユーザー数で決まる
+    assert_eq!(key(1, -0.0, 10, 0).cmp(&key(2, 0.0, 5, 0)), Ordering::Less);
+    assert_eq!(order(vec![key(2, 0.0, 5, 0), key(1, -0.0, 10, 0)]), [1, 2]);
+}
+
+#[test]
+fn top_n_truncates_and_numbers_from_one() {
+    let keys: Vec<RankingKey> = (1..=5).map(|i| key(i, i as f64, 0, 0)).collect();
+    let ranked = rank_top_n(keys.clone(), 3);
+    assert_eq!(
+        ranked,
+        vec![
+            RankedPost { rank: 1, post_id: 5, score: 5.0 },
+            RankedPost { rank: 2, post_id: 4, score: 4.0 },
+            RankedPost { rank: 3, post_id: 3, score: 3.0 },
+        ]
+    );
+    assert_eq!(rank_top_n(keys.clone(), 10).len(), 5);
+    assert!(rank_top_n(keys, 0).is_empty());
+    assert!(rank_top_n(Vec::new(), 3).is_empty());
+}
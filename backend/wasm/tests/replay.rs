@@ -0,0 +1,184 @@
+//! スコア設定の変更によるランキングの変化の再現のテスト
+
+use std::process::Command;
+use std::sync::Arc;
+use std::{env, fs};
+use trend_calculator::{
+    compare_rankings, parse_snapshot, replay_snapshot, BatchError, FixedClock, Period, PeriodRanking, RankChange,
+    RankedPost, ScoringConfig, TrendCalculator, TrendError,
+};
+
+const HOUR: u64 = 60 * 60 * 1000;
+const NOW: u64 = 1_788_000_000_000;
+
+/// 投稿IDの順に1位から並べたランキング
+fn ranking(post_ids: &[u32]) -> PeriodRanking {
+    PeriodRanking {
+        period: Period::Daily,
+        ranking: post_ids
+            .iter()
+            .enumerate()
+            .map(|(i, post_id)| RankedPost {
+                rank: i as u32 + 1,
+                post_id: *post_id,
+                score: (1000 - i) as f64,
+            })
+            .collect(),
+        errors: Vec::new(),
+    }
+}
+
+fn snapshot() -> String {
+    let direct = format!(
+        r#"{{"post_id": 1, "direct": {{"view_increase": 120, "unique_users": 80, "like_increase": 6,
+            "bookmark_count": 3, "comment_increase": 2, "previous_increase_rate": 2.0,
+            "current_increase_rate": 5.0, "total_views_all_time": 1000,
+            "total_unique_users_all_time": 400, "last_updated": {}}}}}"#,
+        NOW - HOUR
+    )
+    .replace('\n', " ");
+    let hll = format!(
+        r#"{{"post_id": 2, "hll": {{"unique_users": 50, "view_count": 200, "previous_view_count": 150,
+            "view_count_per_hour": 8.0, "like_count": 10, "comment_count": 2, "bookmark_count": 4,
+            "last_activity_time": {}}}}}"#,
+        NOW - 2 * HOUR
+    )
+    .replace('\n', " ");
+    let events: Vec<String> = (0..40)
+        .map(|i| {
+            format!(
+                r#"{{"timestamp": {}, "user_id": {}, "engagement_score": 0.5, "event_type": null}}"#,
+                NOW - (i % 10) * HOUR,
+                i
+            )
+        })
+        .collect();
+    let trend = format!(r#"{{"post_id": 3, "events": [{}]}}"#, events.join(","));
+    format!("{}\n\n{}\n{}\n", direct, hll, trend)
+}
+
+#[test]
+fn snapshot_lines_are_parsed_and_routed_by_input() {
+    let posts = parse_snapshot(&snapshot()).unwrap();
+    assert_eq!(posts.len(), 3);
+
+    let config = Arc::new(ScoringConfig::default());
+    let replayed = replay_snapshot(&posts, config, Period::Daily, NOW);
+    assert!(replayed.errors.is_empty());
+    assert_eq!(replayed.ranking.len(), 3);
+
+    let calc = TrendCalculator::new(1, Period::Daily).with_clock(FixedClock(NOW));
+    let score = |post_id: u32| replayed.ranking.iter().find(|r| r.post_id == post_id).unwrap().score;
+    assert_eq!(score(1), calc.calculate_trending_score_direct(posts[0].direct.as_ref().unwrap()).unwrap().score);
+    assert_eq!(score(2), calc.calculate_with_redis_hll_data(posts[1].hll.as_ref().unwrap()).unwrap().score);
+    let mut trend = TrendCalculator::new(3, Period::Daily).with_clock(FixedClock(NOW));
+    trend.set_recent_events(posts[2].events.clone());
+    assert_eq!(score(3), trend.calculate_trend_score().unwrap().score);
+
+    let error = parse_snapshot("{\"post_id\": 1}\n\n{\"post_id\": }").unwrap_err();
+    assert!(matches!(&error, TrendError::Parse { message, .. } if message.starts_with("3行目")), "{:?}", error);
+}
+
+#[test]
+fn identical_configs_report_no_changes() {
+    let posts = parse_snapshot(&snapshot()).unwrap();
+    let config = Arc::new(ScoringConfig::default());
+    let baseline = replay_snapshot(&posts, config.clone(), Period::Daily, NOW);
+    let candidate = replay_snapshot(&posts, config, Period::Daily, NOW);
+    let report = compare_rankings(&baseline, &candidate, 2, 10);
+    assert_eq!(report.compared_posts, 3);
+    assert_eq!((report.kendall_tau, report.spearman_rho), (1.0, 1.0));
+    assert_eq!(report.top_n_overlap, 2);
+    assert!(report.entered.is_empty() && report.exited.is_empty() && report.movers.is_empty());
+}
+
+#[test]
+fn rank_correlation_matches_definitions() {
+    let baseline = ranking(&[1, 2, 3, 4, 5]);
+    let reversed = compare_rankings(&baseline, &ranking(&[5, 4, 3, 2, 1]), 5, 10);
+    assert_eq!((reversed.kendall_tau, reversed.spearman_rho), (-1.0, -1.0));
+
+    // 1組だけ入れ替え: 10組中1組が不一致、Σd² = 2
+    let swapped = compare_rankings(&baseline, &ranking(&[2, 1, 3, 4, 5]), 5, 10);
+    assert!((swapped.kendall_tau - 0.8).abs() < 1e-12);
+    assert!((swapped.spearman_rho - 0.9).abs() < 1e-12);
+
+    // 擬似乱数の並べ替えで、総当たりで数えたケンドールのτと一致する
+    let n = 200u32;
+    let shuffled: Vec<u32> = (0..n).map(|i| (i * 73 + 11) % n + 1).collect();
+    let report = compare_rankings(&ranking(&(1..=n).collect::<Vec<_>>()), &ranking(&shuffled), 10, 10);
+    let position = |post_id: u32| shuffled.iter().position(|p| *p == post_id).unwrap();
+    let mut concordant = 0i64;
+    for a in 1..=n {
+        for b in a + 1..=n {
+            concordant += if position(a) < position(b) { 1 } else { -1 };
+        }
+    }
+    let expected = concordant as f64 / (n * (n - 1) / 2) as f64;
+    assert!((report.kendall_tau - expected).abs() < 1e-12);
+}
+
+#[test]
+fn entries_exits_and_movers_are_reported() {
+    let mut baseline = ranking(&[1, 2, 3, 4, 5, 6, 7]);
+    let mut candidate = ranking(&[6, 2, 1, 3, 5, 4]);
+    baseline.errors.push(BatchError {
+        post_id: 9,
+        error: TrendError::overflow("テスト"),
+    });
+    candidate.errors.push(BatchError {
+        post_id: 7,
+        error: TrendError::overflow("テスト"),
+    });
+
+    let report = compare_rankings(&baseline, &candidate, 3, 3);
+    assert_eq!(report.compared_posts, 6);
+    assert_eq!((report.baseline_errors, report.candidate_errors), (1, 1));
+    assert_eq!(report.top_n_overlap, 2);
+
+    let ids = |changes: &[RankChange]| changes.iter().map(|c| c.post_id).collect::<Vec<_>>();
+    assert_eq!(ids(&report.entered), [6]);
+    assert_eq!(ids(&report.exited), [3]);
+    assert_eq!(report.entered[0].rank_change, 5);
+    assert_eq!((report.exited[0].baseline_rank, report.exited[0].candidate_rank), (Some(3), Some(4)));
+
+    // 上位3件に関わる投稿のうち、変動の大きい順（同じなら投稿ID順）
+    assert_eq!(ids(&report.movers), [6, 1, 3]);
+    assert_eq!(report.movers[1].rank_change, -2);
+}
+
+#[test]
+fn replay_command_rejects_unchanged_or_misspelled_candidates() {
+    let dir = env::temp_dir().join(format!("replay-test-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let write = |name: &str, contents: &str| {
+        let path = dir.join(name);
+        fs::write(&path, contents).unwrap();
+        path
+    };
+    let snapshot = write("snapshot.jsonl", &snapshot());
+    let run = |candidate: &str| {
+        let candidate = write("candidate.json", candidate);
+        Command::new(env!("CARGO_BIN_EXE_replay"))
+            .arg(&snapshot)
+            .arg("--candidate")
+            .arg(&candidate)
+            .args(["--now", &NOW.to_string()])
+            .output()
+            .unwrap()
+    };
+
+    let output = run(r#"{"direct": {"decay_rates": {"daily": 0.5}}}"#);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    // 既定値と同じ設定・綴り誤りのある設定では比較せずに失敗する
+    for (candidate, message) in [
+        (r#"{"direct": {"decay_rates": {"daily": 0.1}}}"#, "変更後の設定が変更前の設定と同じです"),
+        (r#"{"direct": {"decay_rate": {"daily": 0.5}}}"#, "未知の設定項目です: direct.decay_rate"),
+    ] {
+        let output = run(candidate);
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains(message), "{:?}", output);
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,119 @@
+//! 時間窓の段階集約のテスト
+//!
+//! 境界はいずれも日本時間 (UTC+9) で判定される。
+
+use trend_calculator::{diff_windows, rollup_all, rollup_events, rollup_windows, RollupLevel, ViewRecord};
+
+const HOUR: u64 = 60 * 60 * 1000;
+// 2025-06-15T15:30:00Z（日本時間 2025-06-16 00:30、月曜日）
+const MONDAY_JST: u64 = 1_750_001_400_000;
+// 2025-06-15T15:00:00Z（日本時間 2025-06-16 00:00）
+const MONDAY_JST_START: u64 = 1_749_999_600_000;
+
+fn view(user_id: u32, timestamp: u64) -> ViewRecord {
+    ViewRecord { post_id: 1, user_id, timestamp, device: 0, country: 0 }
+}
+
+#[test]
+fn window_boundaries_follow_jst_calendar() {
+    assert_eq!(RollupLevel::Hour.window_start(MONDAY_JST), MONDAY_JST - 30 * 60 * 1000);
+    assert_eq!(RollupLevel::Day.window_start(MONDAY_JST), MONDAY_JST_START);
+    assert_eq!(RollupLevel::Day.window_end(MONDAY_JST), MONDAY_JST_START + 24 * HOUR - 1);
+    // 月曜日なのでその日が週の開始
+    assert_eq!(RollupLevel::Week.window_start(MONDAY_JST), MONDAY_JST_START);
+    // 1時間前はまだ日本時間の日曜日なので前週
+    assert_eq!(RollupLevel::Week.window_start(MONDAY_JST - HOUR), MONDAY_JST_START - 7 * 24 * HOUR);
+    // 2025-05-31T15:00:00Z = 日本時間 2025-06-01 00:00
+    assert_eq!(RollupLevel::Month.window_start(MONDAY_JST), 1_748_703_600_000);
+    // 2025-06-30T15:00:00Z = 日本時間 2025-07-01 00:00
+    assert_eq!(RollupLevel::Month.window_end(MONDAY_JST), 1_751_295_600_000 - 1);
+    // 2024-12-31T15:00:00Z = 日本時間 2025-01-01 00:00
+    assert_eq!(RollupLevel::Year.window_start(MONDAY_JST), 1_735_657_200_000);
+    assert_eq!(RollupLevel::Year.window_end(MONDAY_JST), 1_767_193_200_000 - 1);
+}
+
+#[test]
+fn events_roll_up_into_hours_with_distinct_users() {
+    let events = vec![view(1, MONDAY_JST), view(1, MONDAY_JST + 60_000), view(2, MONDAY_JST + 120_000), view(3, MONDAY_JST + HOUR)];
+    let hours = rollup_events(&events);
+
+    assert_eq!(hours.len(), 2);
+    assert_eq!((hours[0].window.metrics.unique_users, hours[0].window.metrics.total_views), (2, 3));
+    assert_eq!((hours[1].window.metrics.unique_users, hours[1].window.metrics.total_views), (1, 1));
+}
+
+#[test]
+fn coarser_windows_sum_views_and_union_users() {
+    let events: Vec<ViewRecord> = (0..30).map(|i| view(i % 4, MONDAY_JST + (i as u64) * 6 * HOUR)).collect();
+    let hours = rollup_events(&events);
+    let days = rollup_windows(&hours, RollupLevel::Day);
+
+    assert_eq!(days.len(), 8);
+    assert_eq!(days[0].window.start_time, MONDAY_JST_START);
+    assert_eq!(days[0].source_count, 4);
+    assert_eq!(days.iter().map(|d| d.window.metrics.total_views).sum::<u32>(), 30);
+    // 1日4件の閲覧はユーザー0〜3なので和集合は4人（最終日は2件のみ）
+    assert!(days[..7].iter().all(|d| d.window.metrics.unique_users == 4));
+    assert_eq!(days[7].window.metrics.unique_users, 2);
+
+    // 日の窓は週の集約にだけ使われる
+    assert!(rollup_windows(&days, RollupLevel::Month).is_empty());
+    let weeks = rollup_windows(&days, RollupLevel::Week);
+    assert_eq!(weeks.len(), 2);
+    assert_eq!(weeks[0].source_count, 7);
+}
+
+#[test]
+fn rerun_is_idempotent_and_detects_changes() {
+    let mut events: Vec<ViewRecord> = (0..10).map(|i| view(i, MONDAY_JST + (i as u64) * 3 * HOUR)).collect();
+    let first = rollup_all(&rollup_events(&events));
+
+    let rerun = diff_windows(&first, &rollup_all(&rollup_events(&events)));
+    assert!(rerun.created.is_empty() && rerun.updated.is_empty());
+    assert_eq!(rerun.unchanged as usize, first.len());
+
+    // 既存の時間に1件追加すると、その時間から年までの窓だけが更新される
+    events.push(view(99, MONDAY_JST + 60_000));
+    let diff = diff_windows(&first, &rollup_all(&rollup_events(&events)));
+    assert!(diff.created.is_empty());
+    let levels: Vec<RollupLevel> = diff.updated.iter().map(|w| w.level).collect();
+    assert_eq!(levels, vec![RollupLevel::Hour, RollupLevel::Day, RollupLevel::Week, RollupLevel::Month, RollupLevel::Year]);
+}
+
+#[test]
+fn windows_without_sketches_fall_back_to_max_users() {
+    let events: Vec<ViewRecord> = (0..4).map(|i| view(i, MONDAY_JST + (i as u64) * HOUR)).collect();
+    let mut hours = rollup_events(&events);
+    hours[0].window.sketch = None;
+
+    let days = rollup_windows(&hours, RollupLevel::Day);
+    assert_eq!(days[0].window.metrics.unique_users, 1);
+    assert!(days[0].window.sketch.is_none());
+}
+
+#[test]
+fn posts_in_the_same_buffer_get_separate_windows() {
+    let mut events: Vec<ViewRecord> = (0..3).map(|i| view(i, MONDAY_JST + (i as u64) * HOUR)).collect();
+    events.push(ViewRecord { post_id: 2, ..view(0, MONDAY_JST) });
+
+    let hours = rollup_events(&events);
+    let keys: Vec<(u32, u64, u32)> = hours
+        .iter()
+        .map(|w| (w.post_id, w.window.start_time, w.window.metrics.total_views))
+        .collect();
+    let hour = MONDAY_JST - 30 * 60 * 1000;
+    assert_eq!(keys, vec![(1, hour, 1), (1, hour + HOUR, 1), (1, hour + 2 * HOUR, 1), (2, hour, 1)]);
+
+    let all = rollup_all(&hours);
+    let days: Vec<_> = all.iter().filter(|w| w.level == RollupLevel::Day).collect();
+    assert_eq!(days.len(), 2);
+    assert_eq!((days[0].post_id, days[0].window.metrics.total_views, days[0].window.metrics.unique_users), (1, 3, 3));
+    assert_eq!((days[1].post_id, days[1].window.metrics.total_views, days[1].window.metrics.unique_users), (2, 1, 1));
+
+    // 変更検知も投稿ごと: 投稿2の閲覧を増やしても投稿1の窓は更新されない
+    events.push(ViewRecord { post_id: 2, ..view(5, MONDAY_JST + 60_000) });
+    let diff = diff_windows(&all, &rollup_all(&rollup_events(&events)));
+    assert!(diff.created.is_empty());
+    assert_eq!(diff.updated.len(), 5);
+    assert!(diff.updated.iter().all(|w| w.post_id == 2));
+}
@@ -0,0 +1,126 @@
+//! 差し替え可能なランキングモデルのテスト
+
+use std::sync::Arc;
+use trend_calculator::{
+    rank_with_scorer, ExponentialDecayScorer, FixedClock, HackerNewsScorer, Metrics, Period, RedditHotScorer,
+    Scorer, ScorerPost, ScoringConfig, ScoringModel, TimeSkewPolicy, TrendCalculator, TrendError, TrendScorer,
+    ViewEvent, WindowMetrics,
+};
+
+const HOUR: u64 = 60 * 60 * 1000;
+const NOW: u64 = 496_667 * HOUR;
+
+fn event(timestamp: u64, user_id: u32, event_type: Option<&str>) -> ViewEvent {
+    ViewEvent {
+        timestamp,
+        user_id,
+        engagement_score: 0.5,
+        event_type: event_type.map(str::to_string),
+    }
+}
+
+/// `views`回の閲覧と1回のいいねを、公開時刻から1時間おきに受けた投稿
+fn post(post_id: u32, published_hours_ago: u64, views: u32) -> ScorerPost {
+    let published_at = NOW - published_hours_ago * HOUR;
+    let mut events: Vec<ViewEvent> = (0..views)
+        .map(|i| event(published_at + (i as u64 % published_hours_ago.max(1)) * HOUR, i, None))
+        .collect();
+    events.push(event(published_at, 0, Some("like")));
+    ScorerPost {
+        post_id,
+        events,
+        windows: Vec::new(),
+        published_at: Some(published_at),
+    }
+}
+
+fn config() -> Arc<ScoringConfig> {
+    Arc::new(ScoringConfig::default())
+}
+
+#[test]
+fn trend_scorer_matches_trend_calculator() {
+    let mut input = post(1, 10, 40);
+    input.windows.push(WindowMetrics {
+        start_time: NOW - 20 * HOUR,
+        end_time: NOW - 14 * HOUR,
+        metrics: Metrics {
+            unique_users: 30,
+            total_views: 90,
+        },
+        sketch: None,
+    });
+
+    let mut calc = TrendCalculator::new(1, Period::Daily).with_clock(FixedClock(NOW));
+    calc.set_recent_events(input.events.clone());
+    calc.set_aggregated_windows(input.windows.clone());
+    let expected = calc.calculate_trend_score().unwrap().score;
+    assert_eq!(TrendScorer::new(config()).score(&input, Period::Daily, NOW).unwrap(), expected);
+}
+
+#[test]
+fn hacker_news_applies_gravity_to_age() {
+    // 閲覧20回（重み1）+ いいね1回（重み3）= 23ポイント、公開から3時間
+    let score = HackerNewsScorer::new(config()).score(&post(1, 3, 20), Period::Daily, NOW).unwrap();
+    assert!((score - 22.0 / 5f64.powf(1.8)).abs() < 1e-12);
+
+    // 同じポイントなら新しい投稿が上
+    let newer = HackerNewsScorer::new(config()).score(&post(2, 2, 20), Period::Daily, NOW).unwrap();
+    assert!(newer > score);
+}
+
+#[test]
+fn reddit_hot_trades_log_points_for_age() {
+    let scorer = RedditHotScorer::new(config());
+    let score = scorer.score(&post(1, 3, 97), Period::Daily, NOW).unwrap();
+    let seconds = ((NOW - 3 * HOUR) / 1000) as f64 - 1_134_028_003.0;
+    assert!((score - (2.0 + seconds / 45_000.0)).abs() < 1e-6);
+
+    // ポイント10倍の差（log10で1）は、公開時刻の45000秒（12.5時間）の差に相当する
+    let older = scorer.score(&post(1, 20, 997), Period::Daily, NOW).unwrap();
+    let newer = scorer.score(&post(2, 7, 97), Period::Daily, NOW).unwrap();
+    assert!((older - newer - (1.0 - 13.0 / 12.5)).abs() < 1e-6, "{} {}", older, newer);
+    assert!(newer > older);
+}
+
+#[test]
+fn exponential_decay_halves_each_half_life() {
+    let input = ScorerPost {
+        post_id: 1,
+        events: vec![event(NOW, 1, None), event(NOW - 6 * HOUR, 2, None), event(NOW - 12 * HOUR, 3, Some("bookmark"))],
+        windows: Vec::new(),
+        published_at: None,
+    };
+    // 日次の半減期は6時間: 1 + 0.5 + 5 × 0.25
+    let score = ExponentialDecayScorer::new(config()).score(&input, Period::Daily, NOW).unwrap();
+    assert_eq!(score, 2.75);
+}
+
+#[test]
+fn models_are_selectable_per_ranking() {
+    // 古く閲覧の多い投稿と、新しく閲覧の少ない投稿
+    let posts = [post(1, 20, 200), post(2, 1, 30)];
+    let top = |model: ScoringModel| {
+        let ranking = rank_with_scorer(model.scorer(config()).as_ref(), &posts, Period::Daily, 2, NOW);
+        assert!(ranking.errors.is_empty());
+        ranking.ranking[0].post_id
+    };
+    assert_eq!(top(ScoringModel::ExponentialDecay), 1);
+    assert_eq!(top(ScoringModel::HackerNews), 2);
+
+    for model in ScoringModel::ALL {
+        assert_eq!(model.to_string().parse::<ScoringModel>().unwrap(), model);
+        assert_eq!(model.scorer(config()).name(), model.to_string());
+    }
+    assert_eq!("HN".parse::<ScoringModel>().unwrap(), ScoringModel::HackerNews);
+    assert!("pagerank".parse::<ScoringModel>().is_err());
+
+    // 計算に失敗した投稿はエラーとして記録する
+    let mut future = post(3, 1, 5);
+    future.events.push(event(NOW + HOUR, 9, None));
+    let scorer = TrendScorer::new(config()).with_skew_policy(TimeSkewPolicy::Reject);
+    let ranking = rank_with_scorer(&scorer, &[posts[0].clone(), future], Period::Daily, 5, NOW);
+    assert_eq!(ranking.ranking.len(), 1);
+    assert_eq!(ranking.errors.len(), 1);
+    assert!(matches!(ranking.errors[0].error, TrendError::FutureTimestamp { .. }));
+}
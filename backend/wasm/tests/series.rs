@@ -0,0 +1,92 @@
+//! シリーズの急上昇スコア計算のテスト
+//!
+//! 期待値は seriesTrendingCalculator.js の`calculateSeriesTrendingScore`で
+//! 同じ入力・同じ現在時刻から計算したもの。
+
+use trend_calculator::{FixedClock, FollowerSnapshot, Period, SeriesTrendCalculator, SeriesTrendData};
+
+const HOUR: u64 = 60 * 60 * 1000;
+const NOW: u64 = 1_788_000_000_000;
+
+fn calculator(period: Period) -> SeriesTrendCalculator {
+    SeriesTrendCalculator::new(period).with_clock(FixedClock(NOW))
+}
+
+#[test]
+fn daily_score_matches_js_implementation() {
+    let data = SeriesTrendData {
+        series_id: 10,
+        episode_scores: vec![12.5, 30.0, 0.0, 7.25],
+        episode_count: 4,
+        current_follower_count: 58,
+        follower_history: vec![
+            FollowerSnapshot { timestamp: NOW - 48 * HOUR, follower_count: 31 },
+            FollowerSnapshot { timestamp: NOW - 25 * HOUR, follower_count: 40 },
+            FollowerSnapshot { timestamp: NOW - 3 * HOUR, follower_count: 55 },
+        ],
+        previous_follower_rate: Some(0.2),
+        updated_at: NOW - 10 * HOUR,
+    };
+
+    let result = calculator(Period::Daily).calculate(&data).unwrap();
+    assert_eq!(result.follower_increase, 18);
+    assert_eq!(result.score, 422.09);
+}
+
+#[test]
+fn weekly_score_without_previous_rate_matches_js_implementation() {
+    let data = SeriesTrendData {
+        series_id: 11,
+        episode_scores: vec![40.0],
+        episode_count: 12,
+        current_follower_count: 5,
+        follower_history: Vec::new(),
+        previous_follower_rate: None,
+        updated_at: NOW - 30 * HOUR,
+    };
+
+    let result = calculator(Period::Weekly).calculate(&data).unwrap();
+    assert_eq!(result.episode_average_score, 40.0);
+    assert_eq!(result.score, 96.04);
+}
+
+#[test]
+fn inactive_series_scores_zero() {
+    let data = SeriesTrendData {
+        series_id: 12,
+        episode_scores: Vec::new(),
+        episode_count: 0,
+        current_follower_count: 3,
+        follower_history: vec![FollowerSnapshot { timestamp: NOW - 400 * 24 * HOUR, follower_count: 9 }],
+        previous_follower_rate: None,
+        updated_at: NOW,
+    };
+
+    let result = calculator(Period::Yearly).calculate(&data).unwrap();
+    assert_eq!(result.follower_increase, 0);
+    assert_eq!(result.score, 0.0);
+}
+
+#[test]
+fn non_positive_previous_rate_falls_back_to_minimum_unlike_js() {
+    let data = |previous_follower_rate| SeriesTrendData {
+        series_id: 13,
+        episode_scores: vec![20.0],
+        episode_count: 3,
+        current_follower_count: 30,
+        follower_history: vec![FollowerSnapshot { timestamp: NOW - 30 * HOUR, follower_count: 20 }],
+        previous_follower_rate,
+        updated_at: NOW - 2 * HOUR,
+    };
+    let score = |rate| calculator(Period::Daily).calculate(&data(rate)).unwrap().score;
+
+    // JSの`|| 0.01`では負の値がそのまま使われ、加速度が負になってスコアが下がるかNaNになる。
+    // ここでは0・負の値・省略をすべて下限の0.01として扱い、有限のスコアにする
+    let minimum = score(None);
+    assert!(minimum.is_finite());
+    for rate in [Some(0.0), Some(-0.5), Some(-0.01)] {
+        assert_eq!(score(rate), minimum, "{:?}", rate);
+    }
+    assert_eq!(score(Some(0.01)), minimum);
+    assert!(score(Some(1.0)) < minimum);
+}
@@ -0,0 +1,134 @@
+//! イベントを逐次取り込むトレンド計算機のテスト
+
+use trend_calculator::{
+    FixedClock, HyperLogLog, Metrics, Period, StreamingTrendCalculator, TrendCalculator, TrendStats, ViewEvent,
+    WindowMetrics,
+};
+
+const HOUR: u64 = 60 * 60 * 1000;
+/// 1時間の境界に揃えた現在時刻
+const NOW: u64 = 496_667 * HOUR;
+
+fn events() -> Vec<ViewEvent> {
+    (0..300u64)
+        .map(|i| ViewEvent {
+            // 30時間分（一部は日次の期間外）
+            timestamp: NOW - 30 * HOUR + i * 6 * 60 * 1000 + i % 7,
+            user_id: (i * 7 % 90) as u32,
+            engagement_score: (i % 5) as f64 * 0.25,
+            event_type: match i % 10 {
+                0 => Some("like".to_string()),
+                3 => Some("comment".to_string()),
+                6 => Some("bookmark".to_string()),
+                _ => None,
+            },
+        })
+        .collect()
+}
+
+fn windows() -> Vec<WindowMetrics> {
+    (0..3)
+        .map(|i| WindowMetrics {
+            start_time: NOW - (20 - i * 6) * HOUR,
+            end_time: NOW - (14 - i * 6) * HOUR - 1,
+            metrics: Metrics {
+                unique_users: 40 + i as u32,
+                total_views: 120 + i as u32 * 10,
+            },
+            sketch: None,
+        })
+        .collect()
+}
+
+fn batch_score(events: Vec<ViewEvent>, windows: Vec<WindowMetrics>) -> TrendStats {
+    let mut calc = TrendCalculator::new(1, Period::Daily).with_clock(FixedClock(NOW));
+    calc.set_recent_events(events);
+    calc.set_aggregated_windows(windows);
+    calc.calculate_trend_score().unwrap()
+}
+
+fn streaming() -> StreamingTrendCalculator {
+    let mut stream = StreamingTrendCalculator::new(1, Period::Daily).with_clock(FixedClock(NOW));
+    for event in events() {
+        assert!(stream.push_event(&event));
+    }
+    for window in windows() {
+        assert!(stream.push_window(window));
+    }
+    stream
+}
+
+fn assert_same(actual: &TrendStats, expected: &TrendStats) {
+    assert_eq!(actual.score, expected.score);
+    assert_eq!(actual.unique_users, expected.unique_users);
+    assert_eq!(actual.growth_rate, expected.growth_rate);
+    assert_eq!(actual.momentum, expected.momentum);
+    assert!((actual.engagement - expected.engagement).abs() < 1e-9);
+}
+
+#[test]
+fn streaming_score_matches_full_recalculation() {
+    let stream = streaming();
+    assert_eq!(stream.event_count(), 300);
+    assert_eq!(stream.hour_count(), 30);
+    assert_eq!(stream.window_count(), 3);
+
+    let expected = batch_score(events(), windows());
+    assert_same(&stream.calculate_trend_score().unwrap(), &expected);
+}
+
+#[test]
+fn evicted_data_is_dropped_and_late_events_rejected() {
+    let mut stream = streaming();
+    let cutoff = NOW - 10 * HOUR;
+    stream.evict_before(cutoff);
+
+    assert_eq!(stream.hour_count(), 10);
+    assert_eq!(stream.window_count(), 2);
+    assert!(!stream.push_event(&ViewEvent {
+        timestamp: cutoff - 1,
+        user_id: 1,
+        engagement_score: 0.0,
+        event_type: None,
+    }));
+
+    let kept_events = events().into_iter().filter(|e| e.timestamp >= cutoff).collect();
+    let kept_windows = windows().into_iter().filter(|w| w.end_time >= cutoff).collect();
+    assert_same(&stream.calculate_trend_score().unwrap(), &batch_score(kept_events, kept_windows));
+}
+
+#[test]
+fn pushing_a_window_with_the_same_start_replaces_it() {
+    let mut stream = streaming();
+    let mut window = windows()[0].clone();
+    window.metrics.total_views = 1000;
+    stream.push_window(window.clone());
+    assert_eq!(stream.window_count(), 3);
+
+    let mut expected_windows = windows();
+    expected_windows[0] = window;
+    assert_same(&stream.calculate_trend_score().unwrap(), &batch_score(events(), expected_windows));
+}
+
+#[test]
+fn busy_hours_switch_to_sketches() {
+    let mut stream = StreamingTrendCalculator::new(1, Period::Daily).with_clock(FixedClock(NOW));
+    // スケッチ付きの時間窓があるとユニークユーザー数は和集合から求める
+    stream.push_window(WindowMetrics {
+        start_time: NOW - 3 * HOUR,
+        end_time: NOW - 2 * HOUR - 1,
+        metrics: Metrics::default(),
+        sketch: Some(HyperLogLog::new()),
+    });
+    for user_id in 0..20_000u32 {
+        stream.push_event(&ViewEvent {
+            timestamp: NOW - HOUR + user_id as u64,
+            user_id,
+            engagement_score: 0.0,
+            event_type: None,
+        });
+    }
+
+    let unique_users = stream.calculate_trend_score().unwrap().unique_users as f64;
+    assert!((unique_users - 20_000.0).abs() / 20_000.0 < 0.02);
+}
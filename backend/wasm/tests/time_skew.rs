@@ -0,0 +1,142 @@
+//! 時計ずれ（未来のタイムスタンプ）に対する時間計算のテスト
+
+use trend_calculator::{
+    DirectCalculationData, FixedClock, Metrics, Period, RedisHllData, TimeSkewPolicy,
+    TrendCalculator, TrendError, ViewEvent, WindowMetrics,
+};
+
+const HOUR: u64 = 60 * 60 * 1000;
+const NOW: u64 = 1_788_000_000_000;
+
+fn direct_data(last_updated: u64) -> DirectCalculationData {
+    DirectCalculationData {
+        view_increase: 120,
+        unique_users: 80,
+        like_increase: 6,
+        bookmark_count: 3,
+        comment_increase: 2,
+        previous_increase_rate: 2.0,
+        current_increase_rate: 5.0,
+        total_views_all_time: 1000,
+        total_unique_users_all_time: 400,
+        last_updated,
+    }
+}
+
+fn hll_data(last_activity_time: u64) -> RedisHllData {
+    RedisHllData {
+        unique_users: 50,
+        view_count: 200,
+        previous_view_count: 150,
+        view_count_per_hour: 8.0,
+        like_count: 10,
+        comment_count: 2,
+        bookmark_count: 4,
+        last_activity_time,
+    }
+}
+
+fn event(timestamp: u64, user_id: u32) -> ViewEvent {
+    ViewEvent {
+        timestamp,
+        user_id,
+        engagement_score: 1.0,
+        event_type: None,
+    }
+}
+
+fn calculator() -> TrendCalculator {
+    TrendCalculator::new(1, Period::Daily).with_clock(FixedClock(NOW))
+}
+
+#[test]
+fn direct_clamps_future_last_updated_to_no_decay() {
+    let calc = calculator();
+    let future = calc.calculate_trending_score_direct(&direct_data(NOW + 3 * HOUR)).unwrap();
+    let present = calc.calculate_trending_score_direct(&direct_data(NOW)).unwrap();
+
+    assert_eq!(future.time_decay, 1.0);
+    assert_eq!(future, present);
+}
+
+#[test]
+fn direct_rejects_future_last_updated_when_configured() {
+    let calc = calculator().with_skew_policy(TimeSkewPolicy::Reject);
+    let err = calc.calculate_trending_score_direct(&direct_data(NOW + 1)).unwrap_err();
+
+    assert_eq!(err, TrendError::FutureTimestamp { timestamp: NOW + 1, now: NOW });
+}
+
+#[test]
+fn hll_clamps_future_last_activity() {
+    let calc = calculator();
+    let future = calc.calculate_with_redis_hll_data(&hll_data(NOW + 24 * HOUR)).unwrap();
+    let present = calc.calculate_with_redis_hll_data(&hll_data(NOW)).unwrap();
+
+    assert!(future.score.is_finite());
+    assert_eq!(future, present);
+}
+
+#[test]
+fn hll_rejects_future_last_activity_when_configured() {
+    let calc = calculator().with_skew_policy(TimeSkewPolicy::Reject);
+    let err = calc.calculate_with_redis_hll_data(&hll_data(NOW + HOUR)).unwrap_err();
+
+    assert_eq!(err.code(), "FUTURE_TIMESTAMP");
+}
+
+#[test]
+fn trend_score_tolerates_future_events() {
+    let mut calc = calculator();
+    calc.set_recent_events(vec![
+        event(NOW - 2 * HOUR, 1),
+        event(NOW - HOUR, 2),
+        event(NOW + 5 * HOUR, 3),
+    ]);
+
+    let stats = calc.calculate_trend_score().unwrap();
+    assert!(stats.score.is_finite());
+    assert!(stats.score >= 0.0);
+
+    calc.set_skew_policy(TimeSkewPolicy::Reject);
+    let err = calc.calculate_trend_score().unwrap_err();
+    assert_eq!(err, TrendError::FutureTimestamp { timestamp: NOW + 5 * HOUR, now: NOW });
+}
+
+#[test]
+fn trend_score_ignores_future_windows() {
+    let mut calc = calculator();
+    calc.set_aggregated_windows(vec![WindowMetrics {
+        start_time: NOW + HOUR,
+        end_time: NOW + 2 * HOUR,
+        metrics: Metrics {
+            unique_users: 10,
+            total_views: 30,
+        },
+        sketch: None,
+    }]);
+
+    let stats = calc.calculate_trend_score().unwrap();
+    assert!(stats.score.is_finite());
+}
+
+#[test]
+fn trend_score_near_epoch_does_not_underflow() {
+    for period in Period::STANDARD {
+        let mut calc = TrendCalculator::new(7, period).with_clock(FixedClock(HOUR));
+        calc.set_recent_events(vec![event(0, 1), event(HOUR / 2, 2)]);
+
+        let stats = calc.calculate_trend_score().unwrap();
+        assert!(stats.score.is_finite());
+    }
+
+    let calc = TrendCalculator::new(7, Period::Daily).with_clock(FixedClock(0));
+    assert!(calc.calculate_trend_score().unwrap().score.is_finite());
+}
+
+#[test]
+fn skew_policy_parses_from_string() {
+    assert_eq!("clamp".parse::<TimeSkewPolicy>().unwrap(), TimeSkewPolicy::Clamp);
+    assert_eq!("reject".parse::<TimeSkewPolicy>().unwrap(), TimeSkewPolicy::Reject);
+    assert!("ignore".parse::<TimeSkewPolicy>().is_err());
+}
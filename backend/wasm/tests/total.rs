@@ -0,0 +1,60 @@
+//! 総合ランキングのスコア計算のテスト
+//!
+//! 期待値は totalPostRankingCalculator.js / totalSeriesRankingCalculator.js の
+//! 計算式で同じ入力から計算したもの。
+
+use trend_calculator::{TotalPostInput, TotalRankCalculator, TotalSeriesInput};
+
+fn post(post_id: u32, comment_count: u32, bookmark_count: u32, like_count: u32, unique_viewers: u32) -> TotalPostInput {
+    TotalPostInput { post_id, comment_count, bookmark_count, like_count, unique_viewers }
+}
+
+#[test]
+fn post_score_matches_js_implementation() {
+    let calculator = TotalRankCalculator::new();
+
+    let score = calculator.score_post(&post(1, 3, 2, 10, 40));
+    assert_eq!(score.unique_user_factor, 1.2);
+    assert_eq!(score.score, 30.6);
+
+    // 200人以上はユニークユーザー係数が2.0で頭打ち
+    let score = calculator.score_post(&post(2, 0, 0, 0, 300));
+    assert_eq!(score.unique_user_factor, 2.0);
+    assert_eq!(score.score, 60.0);
+}
+
+#[test]
+fn series_score_matches_js_implementation() {
+    let calculator = TotalRankCalculator::new();
+
+    let completed = TotalSeriesInput {
+        series_id: 1,
+        follower_count: 9,
+        episode_count: 5,
+        average_post_score: 8.5,
+        is_completed: true,
+    };
+    let score = calculator.score_series(&completed);
+    assert_eq!(score.follower_score, 10.0);
+    assert_eq!(score.score, 10.34);
+
+    // スコアが0のシリーズも下限値でランキングに載る
+    let empty = TotalSeriesInput {
+        series_id: 2,
+        follower_count: 0,
+        episode_count: 0,
+        average_post_score: 0.0,
+        is_completed: false,
+    };
+    assert_eq!(calculator.score_series(&empty).score, 0.01);
+}
+
+#[test]
+fn ranking_breaks_ties_by_unique_viewers_then_post_id() {
+    let inputs = vec![post(3, 0, 0, 10, 0), post(1, 0, 0, 1, 90), post(2, 0, 0, 1, 90), post(4, 1, 0, 0, 0)];
+
+    let ranking = TotalRankCalculator::new().rank_posts(&inputs, 3);
+    assert_eq!(ranking.scores.len(), 4);
+    let order: Vec<u32> = ranking.ranking.iter().map(|r| r.post_id).collect();
+    assert_eq!(order, vec![1, 2, 3]);
+}
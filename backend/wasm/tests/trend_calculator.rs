@@ -0,0 +1,378 @@
+//! 3つの計算方法（calculate_trend_score・calculate_trending_score_direct・
+//! calculate_with_redis_hll_data）の回帰テストと性質のテスト
+//!
+//! 回帰テストは`tests/golden/<名前>.input.json`の入力を固定時刻・全期間で計算し、
+//! `tests/golden/<名前>.golden.json`と比べる。スコアの計算を意図して変更した場合は
+//! `UPDATE_GOLDEN=1 cargo test --test trend_calculator`で期待値を更新し、差分を確認する。
+//!
+//! 性質のテストは固定のシードの擬似乱数で入力を作り、スコアが有限かつ非負であること、
+//! いいね・本棚追加について単調であること、経過時間について減衰が単調であることを確かめる。
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+use std::{env, fs};
+use trend_calculator::{
+    DirectCalculationData, FixedClock, Period, RedisHllData, TrendCalculator, TrendError, TrendingResult, ViewEvent,
+    WindowMetrics,
+};
+
+const HOUR: u64 = 60 * 60 * 1000;
+const NOW: u64 = 1_788_000_000_000;
+const CASES: usize = 300;
+
+// ---- 回帰テスト ----
+
+#[derive(Deserialize)]
+struct TrendInput {
+    post_id: u32,
+    windows: Vec<WindowMetrics>,
+    events: Vec<ViewEvent>,
+}
+
+#[derive(Deserialize)]
+struct DataInput<T> {
+    post_id: u32,
+    data: T,
+}
+
+#[derive(Serialize)]
+struct GoldenEntry<T> {
+    post_id: u32,
+    period: Period,
+    result: T,
+}
+
+fn golden_path(name: &str, kind: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(format!("{}.{}.json", name, kind))
+}
+
+fn read_input<T: for<'de> Deserialize<'de>>(name: &str) -> T {
+    let path = golden_path(name, "input");
+    let text = fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}を読み込めません: {}", path.display(), e));
+    serde_json::from_str(&text).unwrap()
+}
+
+/// 数値は相対誤差1e-9まで許し、それ以外は完全に一致することを確かめる
+fn assert_json_eq(actual: &Value, expected: &Value, path: &str) {
+    match (actual, expected) {
+        (Value::Number(a), Value::Number(e)) => {
+            let (a, e) = (a.as_f64().unwrap(), e.as_f64().unwrap());
+            assert!((a - e).abs() <= 1e-9 * e.abs().max(1.0), "{}: {} != {}", path, a, e);
+        }
+        (Value::Array(a), Value::Array(e)) => {
+            assert_eq!(a.len(), e.len(), "{}: 要素数が異なります", path);
+            for (i, (a, e)) in a.iter().zip(e).enumerate() {
+                assert_json_eq(a, e, &format!("{}[{}]", path, i));
+            }
+        }
+        (Value::Object(a), Value::Object(e)) => {
+            let keys = |o: &serde_json::Map<String, Value>| o.keys().cloned().collect::<Vec<_>>();
+            assert_eq!(keys(a), keys(e), "{}: キーが異なります", path);
+            for (key, e) in e {
+                assert_json_eq(&a[key], e, &format!("{}.{}", path, key));
+            }
+        }
+        _ => assert_eq!(actual, expected, "{}", path),
+    }
+}
+
+/// 期待値のファイルと比べる（`UPDATE_GOLDEN`が設定されていれば書き換える）
+fn assert_golden<T: Serialize>(name: &str, entries: &[GoldenEntry<T>]) {
+    let actual = serde_json::to_value(entries).unwrap();
+    let path = golden_path(name, "golden");
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&path, serde_json::to_string_pretty(&actual).unwrap() + "\n").unwrap();
+        return;
+    }
+    let text = fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("{}を読み込めません（UPDATE_GOLDEN=1で作成）: {}", path.display(), e));
+    assert_json_eq(&actual, &serde_json::from_str(&text).unwrap(), name);
+}
+
+fn calculator(post_id: u32, period: Period) -> TrendCalculator {
+    TrendCalculator::new(post_id, period).with_clock(FixedClock(NOW))
+}
+
+#[test]
+fn trend_score_matches_golden() {
+    let inputs: Vec<TrendInput> = read_input("trend_score");
+    let mut entries = Vec::new();
+    for input in &inputs {
+        for period in Period::STANDARD {
+            let mut calc = calculator(input.post_id, period);
+            calc.set_aggregated_windows(input.windows.clone());
+            calc.set_recent_events(input.events.clone());
+            entries.push(GoldenEntry {
+                post_id: input.post_id,
+                period,
+                result: calc.calculate_trend_score().unwrap(),
+            });
+        }
+    }
+    assert_golden("trend_score", &entries);
+}
+
+#[test]
+fn direct_score_matches_golden() {
+    let inputs: Vec<DataInput<DirectCalculationData>> = read_input("direct");
+    let mut entries = Vec::new();
+    for input in &inputs {
+        for period in Period::STANDARD {
+            entries.push(GoldenEntry {
+                post_id: input.post_id,
+                period,
+                result: calculator(input.post_id, period).calculate_trending_score_direct(&input.data).unwrap(),
+            });
+        }
+    }
+    assert_golden("direct", &entries);
+}
+
+#[test]
+fn hll_score_matches_golden() {
+    let inputs: Vec<DataInput<RedisHllData>> = read_input("hll");
+    let mut entries = Vec::new();
+    for input in &inputs {
+        for period in Period::STANDARD {
+            entries.push(GoldenEntry {
+                post_id: input.post_id,
+                period,
+                result: calculator(input.post_id, period).calculate_with_redis_hll_data(&input.data).unwrap(),
+            });
+        }
+    }
+    assert_golden("hll", &entries);
+}
+
+// ---- 性質のテスト ----
+
+/// 入力の生成に使う擬似乱数（xorshift64*）
+struct Gen(u64);
+
+impl Gen {
+    fn new(seed: u64) -> Self {
+        Gen(seed.max(1))
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// 0以上`max`以下の整数（小さい値が出やすいよう、桁数を先に選ぶ）
+    fn count(&mut self, max: u32) -> u32 {
+        let digits = self.next() % 6;
+        let limit = 10u64.pow(digits as u32).min(max as u64 + 1);
+        (self.next() % limit) as u32
+    }
+
+    /// 0以上`max`未満の実数
+    fn real(&mut self, max: f64) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64 * max
+    }
+
+    fn period(&mut self) -> Period {
+        Period::STANDARD[(self.next() % 4) as usize]
+    }
+
+    /// 現在時刻から`max_hours`時間前までの時刻
+    fn timestamp(&mut self, max_hours: u64) -> u64 {
+        NOW - self.next() % (max_hours * HOUR + 1)
+    }
+
+    /// 増加率（8回に1回は入力エラーになる負の値）
+    fn rate(&mut self) -> f64 {
+        if self.next().is_multiple_of(8) { -self.real(100.0) } else { self.real(100.0) }
+    }
+
+    fn direct(&mut self) -> DirectCalculationData {
+        let total_views = self.count(1_000_000);
+        DirectCalculationData {
+            view_increase: self.count(100_000),
+            unique_users: self.count(100_000),
+            like_increase: self.count(10_000),
+            bookmark_count: self.count(10_000),
+            comment_increase: self.count(10_000),
+            previous_increase_rate: self.rate(),
+            current_increase_rate: self.rate(),
+            total_views_all_time: total_views,
+            total_unique_users_all_time: self.count(total_views),
+            last_updated: self.timestamp(24 * 400),
+        }
+    }
+
+    /// 1時間あたりの閲覧数は0か0.01以上に限る（0.01未満ではモメンタムが-1.0を下回り、
+    /// スコアが負になりうる。この挙動は回帰テストの投稿305で固定している）
+    fn hll(&mut self) -> RedisHllData {
+        let view_count = self.count(1_000_000);
+        RedisHllData {
+            unique_users: self.count(view_count),
+            view_count,
+            previous_view_count: self.count(1_000_000),
+            view_count_per_hour: if self.next().is_multiple_of(4) { 0.01 + self.real(0.99) } else { self.real(5000.0) },
+            like_count: self.count(view_count),
+            comment_count: self.count(view_count),
+            bookmark_count: self.count(view_count),
+            last_activity_time: self.timestamp(24 * 400),
+        }
+    }
+
+    fn events(&mut self) -> Vec<ViewEvent> {
+        let count = self.count(300) as usize;
+        let users = self.count(200) + 1;
+        (0..count)
+            .map(|_| ViewEvent {
+                timestamp: self.timestamp(24 * 40),
+                user_id: (self.next() % users as u64) as u32,
+                engagement_score: self.real(1.0),
+                event_type: match self.next() % 8 {
+                    0 => Some("like".to_string()),
+                    1 => Some("comment".to_string()),
+                    2 => Some("bookmark".to_string()),
+                    _ => None,
+                },
+            })
+            .collect()
+    }
+
+    fn windows(&mut self) -> Vec<WindowMetrics> {
+        (0..self.count(6))
+            .map(|_| {
+                let start_time = self.timestamp(24 * 400);
+                let total_views = self.count(100_000);
+                WindowMetrics {
+                    start_time,
+                    end_time: (start_time + self.next() % (24 * HOUR)).min(NOW),
+                    metrics: trend_calculator::Metrics {
+                        unique_users: self.count(total_views),
+                        total_views,
+                    },
+                    sketch: None,
+                }
+            })
+            .collect()
+    }
+}
+
+fn assert_valid(score: f64, context: &dyn std::fmt::Debug) {
+    assert!(score.is_finite() && score >= 0.0, "スコアが不正です: {} ({:?})", score, context);
+}
+
+/// 直接計算の結果（負の増加率は解析エラーになることを確かめてNoneを返す）
+fn direct_result(calc: &TrendCalculator, data: &DirectCalculationData) -> Option<TrendingResult> {
+    let result = calc.calculate_trending_score_direct(data);
+    if data.previous_increase_rate < 0.0 || data.current_increase_rate < 0.0 {
+        assert!(matches!(result, Err(TrendError::Parse { .. })), "{:?}", data);
+        return None;
+    }
+    Some(result.unwrap())
+}
+
+fn trend_score(period: Period, windows: &[WindowMetrics], events: &[ViewEvent]) -> f64 {
+    let mut calc = calculator(1, period);
+    calc.set_aggregated_windows(windows.to_vec());
+    calc.set_recent_events(events.to_vec());
+    calc.calculate_trend_score().unwrap().score
+}
+
+#[test]
+fn scores_are_finite_and_non_negative() {
+    let mut gen = Gen::new(0x5EED_0001);
+    for _ in 0..CASES {
+        let period = gen.period();
+        let calc = calculator(1, period);
+
+        let direct = gen.direct();
+        if let Some(result) = direct_result(&calc, &direct) {
+            assert_valid(result.score, &direct);
+        }
+        let hll = gen.hll();
+        assert_valid(calc.calculate_with_redis_hll_data(&hll).unwrap().score, &hll);
+        let (windows, events) = (gen.windows(), gen.events());
+        assert_valid(trend_score(period, &windows, &events), &(&windows, events.len()));
+    }
+}
+
+#[test]
+fn scores_are_monotone_in_likes_and_bookmarks() {
+    let mut gen = Gen::new(0x5EED_0002);
+    for _ in 0..CASES {
+        let period = gen.period();
+        let calc = calculator(1, period);
+        let extra = gen.count(1000) + 1;
+
+        let direct = gen.direct();
+        if let Some(base) = direct_result(&calc, &direct).map(|r| r.score) {
+            let mut more = direct.clone();
+            more.like_increase += extra;
+            assert!(calc.calculate_trending_score_direct(&more).unwrap().score >= base, "{:?}", direct);
+            let mut more = direct.clone();
+            more.bookmark_count += extra;
+            assert!(calc.calculate_trending_score_direct(&more).unwrap().score >= base, "{:?}", direct);
+        }
+
+        let hll = gen.hll();
+        let base = calc.calculate_with_redis_hll_data(&hll).unwrap().score;
+        let mut more = hll.clone();
+        more.like_count += extra;
+        assert!(calc.calculate_with_redis_hll_data(&more).unwrap().score >= base, "{:?}", hll);
+        let mut more = hll.clone();
+        more.bookmark_count += extra;
+        assert!(calc.calculate_with_redis_hll_data(&more).unwrap().score >= base, "{:?}", hll);
+
+        // 閲覧イベントをいいね・本棚追加に変えてもスコアは下がらない
+        let (windows, events) = (gen.windows(), gen.events());
+        let base = trend_score(period, &windows, &events);
+        for event_type in ["like", "bookmark"] {
+            let mut upgraded = events.clone();
+            upgraded
+                .iter_mut()
+                .filter(|e| e.event_type.is_none())
+                .take(extra as usize)
+                .for_each(|e| e.event_type = Some(event_type.to_string()));
+            assert!(trend_score(period, &windows, &upgraded) >= base, "{} {:?}", event_type, windows);
+        }
+    }
+}
+
+#[test]
+fn decay_is_non_increasing_in_elapsed_time() {
+    let mut gen = Gen::new(0x5EED_0003);
+    for _ in 0..CASES {
+        let period = gen.period();
+        let calc = calculator(1, period);
+        let older = gen.next() % (48 * HOUR) + 1;
+
+        let direct = gen.direct();
+        let mut stale = direct.clone();
+        stale.last_updated = direct.last_updated.saturating_sub(older);
+        if let (Some(fresh), Some(stale)) = (direct_result(&calc, &direct), direct_result(&calc, &stale)) {
+            assert!(stale.time_decay <= fresh.time_decay && stale.score <= fresh.score, "{:?}", direct);
+        }
+
+        let hll = gen.hll();
+        let mut stale = hll.clone();
+        stale.last_activity_time = hll.last_activity_time.saturating_sub(older);
+        let (fresh, stale) = (
+            calc.calculate_with_redis_hll_data(&hll).unwrap(),
+            calc.calculate_with_redis_hll_data(&stale).unwrap(),
+        );
+        let decay = |stats: &trend_calculator::TrendStats| stats.explanation.factor_named("time_decay").unwrap();
+        assert!(decay(&stale) <= decay(&fresh) && stale.score <= fresh.score, "{:?}", hll);
+
+        // 同じイベントで現在時刻を進めると、時間減衰と鮮度ブーストは大きくならない
+        let events = gen.events();
+        let factors = |now: u64| {
+            let mut calc = TrendCalculator::new(1, period).with_clock(FixedClock(now));
+            calc.set_recent_events(events.clone());
+            let explanation = calc.calculate_trend_score().unwrap().explanation;
+            (explanation.factor_named("time_decay").unwrap(), explanation.factor_named("freshness_boost").unwrap())
+        };
+        let (fresh_decay, fresh_boost) = factors(NOW);
+        let (stale_decay, stale_boost) = factors(NOW + older);
+        assert!(stale_decay <= fresh_decay && stale_boost <= fresh_boost, "{:?}", period);
+    }
+}